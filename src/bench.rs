@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use chessing::{bitboard::BitInt, chess::Chess, game::{Board, GameTemplate}};
+
+use crate::{eval::{display_ablated, EvalTerm}, search::{create_search_info, score_value, search_to_depth, tt::{create_tt, REPLACEMENTS}, MAX_DEPTH}, util::current_time_millis, variants::{configure_search, GameKind, Variant, VARIANTS}};
+
+pub const BENCH_DEPTH: i32 = 6;
+pub const SCALING_NODES: [u64; 6] = [ 1_000, 4_000, 16_000, 64_000, 256_000, 1_024_000 ];
+
+pub struct BenchResult {
+    pub nodes: u64,
+    pub time: u128
+}
+
+pub fn nps(nodes: u64, time: u128) -> u64 {
+    let time = time.max(1) as u64;
+    nodes * 1000 / time
+}
+
+fn bench_boards<T: BitInt, const N: usize>(boards: Vec<Board<T, N>>, variant: &Variant, depth: i32, ablated: &[EvalTerm]) -> BenchResult {
+    let mut nodes = 0;
+    let mut time = 0;
+
+    for mut board in boards {
+        // Fresh tables for every position so results are reproducible
+        let mut info = create_search_info(&mut board);
+        configure_search(&mut info, variant);
+        info.ablated = ablated.to_vec();
+
+        let start = current_time_millis();
+        search_to_depth(&mut info, &mut board, depth, u64::MAX, u64::MAX);
+        time += current_time_millis() - start;
+
+        nodes += info.nodes;
+    }
+
+    BenchResult { nodes, time }
+}
+
+pub fn bench_variant(variant: &Variant, depth: i32, ablated: &[EvalTerm]) -> BenchResult {
+    match variant.game {
+        GameKind::Chess => {
+            let chess = Chess::create::<u64, 6>();
+            let boards = variant.positions.iter()
+                .map(|fen| chess.load(fen))
+                .collect();
+
+            bench_boards(boards, variant, depth, ablated)
+        }
+    }
+}
+
+// OpenBench-style output for the standard chess suite; ablated terms change the node count, so
+// they're named first to keep the signature from being mistaken for the full eval's
+pub fn bench(depth: i32, ablated: &[EvalTerm]) {
+    let result = bench_variant(&VARIANTS[0], depth, ablated);
+
+    if !ablated.is_empty() {
+        println!("ablated {}", display_ablated(ablated));
+    }
+
+    println!("{} nodes {} nps", result.nodes, nps(result.nodes, result.time));
+}
+
+pub fn bench_variants(depth: i32) {
+    let mut nodes = 0;
+    let mut time = 0;
+
+    for variant in VARIANTS {
+        let result = bench_variant(variant, depth, &[]);
+        println!(
+            "{:<16} {:>12} nodes {:>10} nps",
+            variant.name, result.nodes, nps(result.nodes, result.time)
+        );
+
+        nodes += result.nodes;
+        time += result.time;
+    }
+
+    println!("{:<16} {:>12} nodes {:>10} nps", "total", nodes, nps(nodes, time));
+}
+
+// Small enough that the bench suite fills it at the default depth, so the policies differ
+pub const TT_BENCH_ENTRIES: usize = 16_384;
+
+// Tree size at a fixed depth under each TT replacement policy, fewer nodes meaning the table kept
+// the entries that mattered. One table is carried through the suite, like successive searches in a game
+pub fn bench_tt(depth: i32, entries: usize) {
+    let variant = &VARIANTS[0];
+    let chess = match variant.game {
+        GameKind::Chess => Chess::create::<u64, 6>()
+    };
+    let entries = entries.max(1);
+
+    println!("{} entries, depth {}", entries, depth);
+    println!("{:<8} {:>12} {:>10}", "policy", "nodes", "nps");
+
+    for (name, replacement) in REPLACEMENTS {
+        let tt = Arc::new(create_tt(entries));
+        let mut generation = 0;
+        let mut nodes = 0;
+        let mut time = 0;
+
+        for fen in variant.positions {
+            let mut board = chess.load(fen);
+            let mut info = create_search_info(&mut board);
+            configure_search(&mut info, variant);
+            info.tt = tt.clone();
+            info.tt_replacement = replacement;
+            info.tt_generation = generation;
+
+            let start = current_time_millis();
+            search_to_depth(&mut info, &mut board, depth, u64::MAX, u64::MAX);
+            time += current_time_millis() - start;
+
+            nodes += info.nodes;
+            generation = info.tt_generation;
+        }
+
+        println!("{:<8} {:>12} {:>10}", name, nodes, nps(nodes, time));
+    }
+}
+
+// One node limit's worth of searches over the bench suite
+pub struct ScalingPoint {
+    pub limit: u64,
+    pub depth: f64,
+    pub seldepth: f64,
+    pub score: f64,
+    pub nodes: u64,
+    pub time: u128
+}
+
+fn scaling_point(variant: &Variant, limit: u64) -> ScalingPoint {
+    let chess = match variant.game {
+        GameKind::Chess => Chess::create::<u64, 6>()
+    };
+    let mut point = ScalingPoint { limit, depth: 0., seldepth: 0., score: 0., nodes: 0, time: 0 };
+    let mut searched = 0;
+
+    for fen in variant.positions {
+        let mut board = chess.load(fen);
+        let mut info = create_search_info(&mut board);
+        configure_search(&mut info, variant);
+        info.max_nodes = limit;
+
+        let start = current_time_millis();
+        let result = search_to_depth(&mut info, &mut board, MAX_DEPTH, u64::MAX, u64::MAX);
+        point.time += current_time_millis() - start;
+        point.nodes += info.nodes;
+
+        if let Some(result) = result {
+            point.depth += result.depth as f64;
+            point.seldepth += result.seldepth as f64;
+            // Clamped so a found mate doesn't swamp the average
+            point.score += score_value(result.score).clamp(-2000, 2000).abs() as f64;
+            searched += 1;
+        }
+    }
+
+    let searched = searched.max(1) as f64;
+    point.depth /= searched;
+    point.seldepth /= searched;
+    point.score /= searched;
+    point
+}
+
+// How depth and scores grow with nodes, to tell a change that wins at fixed nodes
+// but loses at fixed time (ie. a heavier eval) apart from a real improvement
+pub fn scaling(limits: &[u64]) {
+    let variant = &VARIANTS[0];
+
+    println!("{:>10} {:>7} {:>9} {:>8} {:>12} {:>10}", "nodes", "depth", "seldepth", "|score|", "total nodes", "nps");
+
+    for &limit in limits {
+        let point = scaling_point(variant, limit);
+        println!(
+            "{:>10} {:>7.2} {:>9.2} {:>8.1} {:>12} {:>10}",
+            point.limit, point.depth, point.seldepth, point.score, point.nodes, nps(point.nodes, point.time)
+        );
+    }
+}
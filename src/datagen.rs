@@ -0,0 +1,333 @@
+use std::{collections::HashSet, fs::{self, File, OpenOptions}, io::{self, BufWriter, ErrorKind, Write}, path::Path};
+
+use chessing::{bitboard::BitInt, chess::Chess, game::{Board, GameState, GameTemplate, Team}};
+
+use crate::{eval::evaluate, fen::to_fen, search::{create_search_info, is_noisy, legal_actions, quiescence, quiet::is_quiet, score_value, search_to_depth, SearchInfo, MAX, MIN}, signals::shutdown_requested, util::{current_time_millis, Rng}, variants::{configure_search, find_variant, GameKind, Variant, VARIANTS}};
+
+pub struct DatagenOptions {
+    pub variant: &'static Variant,
+    pub games: u64,
+    pub depth: i32,
+    pub random_plies: usize,
+    // Root noise in centipawns for the plies after the random opening, 0 is off
+    pub noise: i32,
+    pub eval_cap: i32,
+    pub max_plies: usize,
+    pub seed: u64,
+    pub shard_games: u64,
+    pub output: String
+}
+
+// datagen [variant <name>] [games <n>] [depth <plies>] [random <plies>] [noise <cp>] [cap <cp>] [seed <n>] [shard <games>] [output <dir>]
+pub fn parse_datagen_options(args: &[&str]) -> DatagenOptions {
+    let mut options = DatagenOptions {
+        variant: &VARIANTS[0],
+        games: 100,
+        depth: 6,
+        random_plies: 8,
+        noise: 0,
+        eval_cap: 3000,
+        max_plies: 400,
+        seed: current_time_millis() as u64,
+        shard_games: 100,
+        output: "data".to_string()
+    };
+
+    let mut i = 0;
+    while i + 1 < args.len() {
+        let value = args[i + 1];
+
+        match args[i] {
+            "variant" => {
+                match find_variant(value) {
+                    Some(variant) => options.variant = variant,
+                    None => println!("info string unknown variant {}, generating {}", value, options.variant.name)
+                }
+            }
+            "games" => options.games = value.parse().unwrap_or(options.games),
+            "depth" => options.depth = value.parse().unwrap_or(options.depth),
+            "random" => options.random_plies = value.parse().unwrap_or(options.random_plies),
+            "noise" => options.noise = value.parse().unwrap_or(options.noise),
+            "cap" => options.eval_cap = value.parse().unwrap_or(options.eval_cap),
+            "seed" => options.seed = value.parse().unwrap_or(options.seed),
+            "shard" => options.shard_games = value.parse().unwrap_or(options.shard_games).max(1),
+            "output" => options.output = value.to_string(),
+            _ => {}
+        }
+
+        i += 2;
+    }
+
+    options
+}
+
+// A position is only worth training on if it's quiet and the static eval already agrees with qsearch
+fn is_quiet_position<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &mut SearchInfo) -> bool {
+    if !is_quiet(board, info) {
+        return false;
+    }
+
+    let static_eval = evaluate(board, info, 0);
+    let resolved = quiescence(board, info, 0, MIN, MAX);
+
+    static_eval == resolved
+}
+
+fn play_random_opening<T: BitInt, const N: usize>(
+    board: &mut Board<T, N>,
+    info: &mut SearchInfo,
+    rng: &mut Rng,
+    plies: usize
+) -> bool {
+    for _ in 0..plies {
+        let actions = legal_actions(board);
+        if actions.is_empty() {
+            return false;
+        }
+
+        let action = actions[rng.below(actions.len())];
+        board.play(action);
+        info.hashes.push(board.game.rules.hash(board, &info.zobrist));
+    }
+
+    !legal_actions(board).is_empty()
+}
+
+// Plays out one self-play game, returning the kept (fen, white-relative score) pairs and the result for white
+fn play_game<T: BitInt, const N: usize>(
+    board: &mut Board<T, N>,
+    options: &DatagenOptions,
+    rng: &mut Rng,
+    seen: &mut HashSet<u64>
+) -> Option<(Vec<(String, i32)>, f32)> {
+    // Variants without their own eval search with `eval_primitive`, which is what
+    // the resulting data is meant to train a replacement for
+    let mut info = create_search_info(board);
+    configure_search(&mut info, options.variant);
+    info.hashes = vec![ board.game.rules.hash(board, &info.zobrist) ];
+
+    // Noise carries variety past the random opening, for as many plies again
+    info.root_noise = options.noise;
+    info.root_noise_plies = 2 * options.random_plies;
+    info.root_noise_seed = rng.next_u64();
+
+    if !play_random_opening(board, &mut info, rng, options.random_plies) {
+        return None;
+    }
+
+    let mut positions = vec![];
+    let mut ply = options.random_plies;
+
+    let result = loop {
+        let actions = legal_actions(board);
+        match board.game_state(&actions) {
+            GameState::Win(Team::White) => break 1.0,
+            GameState::Win(Team::Black) => break 0.0,
+            GameState::Draw => break 0.5,
+            GameState::Ongoing => {}
+        }
+
+        let hash = board.game.rules.hash(board, &info.zobrist);
+        if ply >= options.max_plies || info.hashes.iter().filter(|&&previous| previous == hash).count() >= 3 {
+            break 0.5;
+        }
+
+        let result = match search_to_depth(&mut info, board, options.depth, u64::MAX, u64::MAX) {
+            Some(result) => result,
+            None => break 0.5
+        };
+
+        let best_move = result.best;
+        let relative = score_value(result.score);
+        let score = match board.state.moving_team {
+            Team::White => relative,
+            Team::Black => -relative
+        };
+
+        let keep = relative.abs() < options.eval_cap
+            && !is_noisy(board, &info, best_move)
+            && is_quiet_position(board, &mut info)
+            && seen.insert(hash);
+
+        if keep {
+            positions.push((to_fen(board, ply / 2 + 1), score));
+        }
+
+        board.play(best_move);
+        info.hashes.push(board.game.rules.hash(board, &info.zobrist));
+        ply += 1;
+    };
+
+    Some((positions, result))
+}
+
+// Standard chess keeps the plain `fen | score | result` layout trainers expect, anything
+// else is tagged with its variant so datasets for different games can't be mixed up
+fn format_position(variant: &Variant, fen: &str, score: i32, result: f32) -> String {
+    if variant.name == "chess" {
+        format!("{} | {} | {:.1}", fen, score, result)
+    } else {
+        format!("{} | {} | {} | {:.1}", variant.name, fen, score, result)
+    }
+}
+
+// What a previous run in the same output directory already finished
+pub struct Manifest {
+    pub variant: String,
+    pub seed: u64,
+    pub shard_games: u64,
+    pub completed: HashSet<u64>
+}
+
+// The manifest is plain `key value` lines; a `shard` line is only appended
+// once that shard's file has been fully written and flushed. No manifest is a fresh run, one
+// that can't be read or parsed is an error rather than something to start over on top of
+fn read_manifest(path: &Path) -> io::Result<Option<Manifest>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error)
+    };
+    let invalid = || io::Error::new(ErrorKind::InvalidData, format!("unreadable datagen manifest {}", path.display()));
+    let mut manifest = Manifest {
+        variant: String::new(),
+        seed: 0,
+        shard_games: 0,
+        completed: HashSet::new()
+    };
+
+    for line in contents.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        match parts.as_slice() {
+            ["variant", variant] => manifest.variant = variant.to_string(),
+            ["seed", seed] => manifest.seed = seed.parse().map_err(|_| invalid())?,
+            ["shard_games", games] => manifest.shard_games = games.parse().map_err(|_| invalid())?,
+            ["shard", shard, ..] => {
+                manifest.completed.insert(shard.parse().map_err(|_| invalid())?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Some(manifest))
+}
+
+fn append_manifest(path: &Path, line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+
+    writeln!(file, "{}", line)?;
+    file.sync_all()
+}
+
+fn run_datagen<T: BitInt, const N: usize, F: Fn() -> Board<T, N>>(options: &mut DatagenOptions, new_board: F) -> io::Result<()> {
+    let output = Path::new(&options.output).to_path_buf();
+    fs::create_dir_all(&output)?;
+
+    let manifest_path = output.join("manifest.txt");
+    let completed = match read_manifest(&manifest_path)? {
+        Some(manifest) => {
+            if manifest.variant != options.variant.name {
+                println!("info string {} holds {} data, not {}", options.output, manifest.variant, options.variant.name);
+                return Ok(());
+            }
+
+            // Resuming has to reuse the original seed and shard layout
+            options.seed = manifest.seed;
+            options.shard_games = manifest.shard_games.max(1);
+            println!("info string resuming datagen, {} shards already complete", manifest.completed.len());
+
+            manifest.completed
+        }
+        None => {
+            append_manifest(&manifest_path, &format!("variant {}", options.variant.name))?;
+            append_manifest(&manifest_path, &format!("seed {}", options.seed))?;
+            append_manifest(&manifest_path, &format!("shard_games {}", options.shard_games))?;
+
+            HashSet::new()
+        }
+    };
+
+    // Deduplication only covers shards generated during this run
+    let mut seen = HashSet::new();
+    let shards = (options.games + options.shard_games - 1) / options.shard_games;
+
+    let start = current_time_millis();
+    let mut total_positions = 0;
+
+    for shard in 0..shards {
+        if completed.contains(&shard) {
+            continue;
+        }
+
+        // Seeded per shard, so a resumed shard replays the same openings
+        let mut rng = Rng::new(options.seed ^ (shard + 1).wrapping_mul(0x9E3779B97F4A7C15));
+
+        let shard_path = output.join(format!("shard-{:04}.txt", shard));
+        let mut writer = BufWriter::new(File::create(&shard_path)?);
+
+        let first_game = shard * options.shard_games;
+        let last_game = (first_game + options.shard_games).min(options.games);
+        let mut shard_positions = 0;
+
+        for game in first_game + 1..=last_game {
+            let mut board = new_board();
+
+            let (positions, result) = match play_game(&mut board, options, &mut rng, &mut seen) {
+                Some(game) => game,
+                None => continue
+            };
+
+            // The game's searches were cut short, so its scores can't be trusted
+            if shutdown_requested() {
+                break;
+            }
+
+            for (fen, score) in &positions {
+                writeln!(writer, "{}", format_position(options.variant, fen, *score, result))?;
+            }
+            shard_positions += positions.len();
+
+            if game % 10 == 0 || game == last_game {
+                let seconds = ((current_time_millis() - start) / 1000).max(1);
+                println!(
+                    "info string datagen {} games {} positions {} ({} pos/s)",
+                    options.variant.name, game, total_positions + shard_positions, 
+                    (total_positions + shard_positions) as u128 / seconds
+                );
+            }
+        }
+
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        total_positions += shard_positions;
+
+        // A partial shard stays out of the manifest and is regenerated on resume
+        if shutdown_requested() {
+            println!("info string datagen interrupted, shard {} will be regenerated on resume", shard);
+            return Ok(());
+        }
+
+        append_manifest(&manifest_path, &format!("shard {} positions {}", shard, shard_positions))?;
+    }
+
+    Ok(())
+}
+
+pub fn datagen(args: &[&str]) {
+    let mut options = parse_datagen_options(args);
+
+    let result = match options.variant.game {
+        GameKind::Chess => {
+            let chess = Chess::create::<u64, 6>();
+            run_datagen(&mut options, || chess.default())
+        }
+    };
+
+    if let Err(error) = result {
+        println!("info string datagen failed: {}", error);
+    }
+}
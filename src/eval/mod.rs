@@ -1,9 +1,10 @@
-use chessing::{bitboard::{BitBoard, BitInt}, game::{Board, Team}};
-use psqt::{BISHOP_EG, BISHOP_EG_WHITE, BISHOP_MG, BISHOP_MG_WHITE, KING_EG, KING_EG_WHITE, KING_MG, KING_MG_WHITE, KNIGHT_EG, KNIGHT_EG_WHITE, KNIGHT_MG, KNIGHT_MG_WHITE, PAWN_EG, PAWN_EG_WHITE, PAWN_MG, PAWN_MG_WHITE, QUEEN_EG, QUEEN_EG_WHITE, QUEEN_MG, QUEEN_MG_WHITE, ROOK_EG, ROOK_EG_WHITE, ROOK_MG, ROOK_MG_WHITE};
+use chessing::{bitboard::BitInt, game::{Board, Team}};
+use psqt::flip_sq;
 
-use crate::search::SearchInfo;
+use crate::{nnue::evaluate_nnue, search::{SearchInfo, MAX, MIN}};
 
 mod psqt;
+pub mod params;
 
 pub fn team_to_move<T: BitInt, const N: usize>(board: &mut Board<T, N>) -> i32 {
     match board.state.moving_team {
@@ -21,6 +22,8 @@ pub const QUEEN: i32 = 950;
 pub const MOBILITY: i32 = 3;
 
 pub const MATERIAL: [ i32; 6 ] = [ PAWN, KNIGHT, BISHOP, ROOK, QUEEN, 0 ];
+// Minor = 1, rook = 2, queen = 4, so the starting position is 24
+pub const PHASE: [ i32; 6 ] = [ 0, 1, 1, 2, 4, 0 ];
 
 // For use in training neural nets on new variants
 pub fn eval_primitive<T: BitInt, const N: usize>(
@@ -59,98 +62,167 @@ pub fn eval_primitive<T: BitInt, const N: usize>(
     score * team_to_move(board)
 }
 
-pub fn eval<T: BitInt, const N: usize>(
+// Classical eval terms that can be switched off, so an SPRT can say what each one is worth.
+// Material always stays, nothing else means much without it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvalTerm {
+    Psqt,
+    Mobility
+}
+
+// Keyed by the option that switches the term off
+pub const EVAL_TERMS: [(&str, EvalTerm, &str); 2] = [
+    ("AblatePSQT", EvalTerm::Psqt, "psqt"),
+    ("AblateMobility", EvalTerm::Mobility, "mobility")
+];
+
+pub fn find_ablation(option: &str) -> Option<EvalTerm> {
+    EVAL_TERMS.iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(option))
+        .map(|&(_, term, _)| term)
+}
+
+pub fn display_ablated(ablated: &[EvalTerm]) -> String {
+    EVAL_TERMS.iter()
+        .filter(|(_, term, _)| ablated.contains(term))
+        .map(|&(_, _, name)| name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Eval is scaled by (HALFMOVE_DAMPING - halfmove clock) / HALFMOVE_DAMPING
+pub const HALFMOVE_DAMPING: usize = 200;
+
+// Positions the network saw little of in training, where the classical eval gets mixed in
+pub const BLEND_IMBALANCE: i32 = 600;
+pub const BLEND_PHASE: i32 = 4;
+
+fn should_blend<T: BitInt, const N: usize>(board: &mut Board<T, N>) -> bool {
+    let mut imbalance = 0;
+    let mut phase = 0;
+
+    for (piece, &value) in MATERIAL.iter().enumerate() {
+        let pieces = board.state.pieces[piece];
+        imbalance += value * (pieces.and(board.state.white).count() as i32 - pieces.and(board.state.black).count() as i32);
+        phase += PHASE[piece] * pieces.count() as i32;
+    }
+
+    imbalance.abs() >= BLEND_IMBALANCE || phase <= BLEND_PHASE
+}
+
+// Bare royals, or one minor piece against a bare royal (KvK, KNvK, KBvK in chess)
+pub fn is_dead_draw<T: BitInt, const N: usize>(board: &Board<T, N>, info: &SearchInfo) -> bool {
+    let mut minors = 0;
+
+    for (piece, pieces) in board.state.pieces.iter().enumerate() {
+        if info.royal_pieces.contains(&piece) || !pieces.is_set() {
+            continue;
+        }
+
+        if !info.minor_pieces.contains(&piece) {
+            return false;
+        }
+
+        minors += pieces.count();
+    }
+
+    minors <= 1
+}
+
+// Variants without a dedicated eval fall back to `eval_primitive`
+pub fn evaluate<T: BitInt, const N: usize>(
     board: &mut Board<T, N>,
     info: &mut SearchInfo,
     ply: usize
 ) -> i32 {
-    let mut score = 0;
-
-    let pawns = board.state.pieces[0];
-    let knights = board.state.pieces[1];
-    let bishops = board.state.pieces[2];
-    let rooks = board.state.pieces[3];
-    let queens = board.state.pieces[4];
-    let kings = board.state.pieces[5];
-
-    let white = board.state.white;
-    let black = board.state.black;
-
-    let white_pawns = pawns.and(white);
-    let black_pawns = pawns.and(black);
+    evaluate_within(board, info, ply, MIN, MAX)
+}
 
-    let white_knights = knights.and(white);
-    let black_knights = knights.and(black);
+// The same eval, which the classical one may cut short when the score is clearly outside
+// (alpha, beta); only for callers that just compare the result against that window
+pub fn evaluate_within<T: BitInt, const N: usize>(
+    board: &mut Board<T, N>,
+    info: &mut SearchInfo,
+    ply: usize,
+    alpha: i32,
+    beta: i32
+) -> i32 {
+    if is_dead_draw(board, info) {
+        return 0;
+    }
 
-    let white_bishops = bishops.and(white);
-    let black_bishops = bishops.and(black);
+    let score = if info.generic {
+        eval_primitive(board, info, ply)
+    } else if let Some(nnue) = &mut info.nnue {
+        let score = evaluate_nnue(board, nnue, ply);
+        let weight = info.params.eval_blend;
+
+        if weight > 0 && should_blend(board) {
+            let classical = eval(board, info, ply, MIN, MAX);
+            (score * (1024 - weight) + classical * weight) / 1024
+        } else {
+            score
+        }
+    } else {
+        eval(board, info, ply, alpha, beta)
+    };
 
-    let white_rooks = rooks.and(white);
-    let black_rooks = rooks.and(black);
+    // Shuffle damping: as the fifty-move counter climbs, any advantage is worth less than making progress
+    let halfmove = info.halfmove.get(ply).copied().unwrap_or(0).min(HALFMOVE_DAMPING) as i32;
+    score * (HALFMOVE_DAMPING as i32 - halfmove) / HALFMOVE_DAMPING as i32
+}
 
-    let white_queens = queens.and(white);
-    let black_queens = queens.and(black);
+pub fn eval<T: BitInt, const N: usize>(
+    board: &mut Board<T, N>,
+    info: &mut SearchInfo,
+    ply: usize,
+    alpha: i32,
+    beta: i32
+) -> i32 {
+    let mut score = 0;
+    let params = &info.eval_params;
 
-    let white_king = kings.and(white);
-    let black_king = kings.and(black);
+    let white = board.state.white;
+    let black = board.state.black;
 
-    let white_material = 
-        (white_pawns.count() as i32 * PAWN) +
-        (white_knights.count() as i32 * KNIGHT) +
-        (white_bishops.count() as i32 * BISHOP) +
-        (white_rooks.count() as i32 * ROOK) +
-        (white_queens.count() as i32 * QUEEN);
+    let mut white_material = 0;
+    let mut black_material = 0;
 
-    let black_material = 
-        (black_pawns.count() as i32 * PAWN) +
-        (black_knights.count() as i32 * KNIGHT) +
-        (black_bishops.count() as i32 * BISHOP) +
-        (black_rooks.count() as i32 * ROOK) +
-        (black_queens.count() as i32 * QUEEN);
+    for (piece, &value) in params.material.iter().enumerate() {
+        let pieces = board.state.pieces[piece];
+        white_material += pieces.and(white).count() as i32 * value;
+        black_material += pieces.and(black).count() as i32 * value;
+    }
 
     score += white_material - black_material;
 
     let total_material = white_material + black_material;
 
-    if total_material > 5000 {
-        score += compute_mg(
-            white_pawns, black_pawns,
-            white_knights, black_knights,
-            white_bishops, black_bishops,
-            white_rooks, black_rooks,
-            white_queens, black_queens,
-            white_king, black_king
-        );
+    if info.ablated.contains(&EvalTerm::Psqt) {
+        // Switched off, see `EVAL_TERMS`
+    } else if total_material > 5000 {
+        score += compute_psqt(board, &params.psqt_mg);
     } else if total_material < 2500 {
-        score += compute_eg(
-            white_pawns, black_pawns,
-            white_knights, black_knights,
-            white_bishops, black_bishops,
-            white_rooks, black_rooks,
-            white_queens, black_queens,
-            white_king, black_king
-        );
+        score += compute_psqt(board, &params.psqt_eg);
     } else {
-        let mg = compute_mg(
-            white_pawns, black_pawns,
-            white_knights, black_knights,
-            white_bishops, black_bishops,
-            white_rooks, black_rooks,
-            white_queens, black_queens,
-            white_king, black_king
-        );
-        let eg = compute_eg(
-            white_pawns, black_pawns,
-            white_knights, black_knights,
-            white_bishops, black_bishops,
-            white_rooks, black_rooks,
-            white_queens, black_queens,
-            white_king, black_king
-        );
+        let mg = compute_psqt(board, &params.psqt_mg);
+        let eg = compute_psqt(board, &params.psqt_eg);
         let weight = total_material - 2500;
         score += (mg * weight + eg * (2500 - weight)) / 2500;
     }
 
+    // Lazy eval: the terms below can't bring a score this far out back into the window
+    if alpha > MIN || beta < MAX {
+        let partial = score * team_to_move(board);
+        let margin = info.params.lazy_eval_margin;
+        info.stats.windowed_evals += 1;
+
+        if partial - margin >= beta || partial + margin <= alpha {
+            info.stats.lazy_evals += 1;
+            return partial;
+        }
+    }
+
     let mut white_mobility = 0;
     let mut black_mobility = 0;
 
@@ -171,60 +243,23 @@ pub fn eval<T: BitInt, const N: usize>(
         }
     } 
 
-    let mobility_bonus = MOBILITY * ((white_mobility as i32)  - (black_mobility as i32));
-    score += mobility_bonus;
+    let mobility_bonus = info.eval_params.mobility * ((white_mobility as i32)  - (black_mobility as i32));
+    if !info.ablated.contains(&EvalTerm::Mobility) {
+        score += mobility_bonus;
+    }
 
     score * team_to_move(board)
 }
 
-fn compute_mg<T: BitInt>(
-    wp: BitBoard<T>, bp: BitBoard<T>,
-    wn: BitBoard<T>, bn: BitBoard<T>,
-    wb: BitBoard<T>, bb: BitBoard<T>,
-    wr: BitBoard<T>, br: BitBoard<T>,
-    wq: BitBoard<T>, bq: BitBoard<T>,
-    wk: BitBoard<T>, bk: BitBoard<T>
-) -> i32 {
-    let mut mg = 0;
-
-    for sq in wp.iter() { mg += PAWN_MG_WHITE[sq as usize]; }
-    for sq in bp.iter() { mg -= PAWN_MG[sq as usize]; }
-    for sq in wn.iter() { mg += KNIGHT_MG_WHITE[sq as usize]; }
-    for sq in bn.iter() { mg -= KNIGHT_MG[sq as usize]; }
-    for sq in wb.iter() { mg += BISHOP_MG_WHITE[sq as usize]; }
-    for sq in bb.iter() { mg -= BISHOP_MG[sq as usize]; }
-    for sq in wr.iter() { mg += ROOK_MG_WHITE[sq as usize]; }
-    for sq in br.iter() { mg -= ROOK_MG[sq as usize]; }
-    for sq in wq.iter() { mg += QUEEN_MG_WHITE[sq as usize]; }
-    for sq in bq.iter() { mg -= QUEEN_MG[sq as usize]; }
-    for sq in wk.iter() { mg += KING_MG_WHITE[sq as usize]; }
-    for sq in bk.iter() { mg -= KING_MG[sq as usize]; }
-
-    mg
-}
+// Tables are from black's side, so white's squares are looked up flipped
+fn compute_psqt<T: BitInt, const N: usize>(board: &Board<T, N>, tables: &[[i32; 64]; 6]) -> i32 {
+    let mut score = 0;
 
-fn compute_eg<T: BitInt>(
-    wp: BitBoard<T>, bp: BitBoard<T>,
-    wn: BitBoard<T>, bn: BitBoard<T>,
-    wb: BitBoard<T>, bb: BitBoard<T>,
-    wr: BitBoard<T>, br: BitBoard<T>,
-    wq: BitBoard<T>, bq: BitBoard<T>,
-    wk: BitBoard<T>, bk: BitBoard<T>
-) -> i32 {
-    let mut eg = 0;
-
-    for sq in wp.iter() { eg += PAWN_EG_WHITE[sq as usize]; }
-    for sq in bp.iter() { eg -= PAWN_EG[sq as usize]; }
-    for sq in wn.iter() { eg += KNIGHT_EG_WHITE[sq as usize]; }
-    for sq in bn.iter() { eg -= KNIGHT_EG[sq as usize]; }
-    for sq in wb.iter() { eg += BISHOP_EG_WHITE[sq as usize]; }
-    for sq in bb.iter() { eg -= BISHOP_EG[sq as usize]; }
-    for sq in wr.iter() { eg += ROOK_EG_WHITE[sq as usize]; }
-    for sq in br.iter() { eg -= ROOK_EG[sq as usize]; }
-    for sq in wq.iter() { eg += QUEEN_EG_WHITE[sq as usize]; }
-    for sq in bq.iter() { eg -= QUEEN_EG[sq as usize]; }
-    for sq in wk.iter() { eg += KING_EG_WHITE[sq as usize]; }
-    for sq in bk.iter() { eg -= KING_EG[sq as usize]; }
-
-    eg
+    for (piece, table) in tables.iter().enumerate() {
+        let pieces = board.state.pieces[piece];
+        for sq in pieces.and(board.state.white).iter() { score += table[flip_sq(sq as u8) as usize]; }
+        for sq in pieces.and(board.state.black).iter() { score -= table[sq as usize]; }
+    }
+
+    score
 }
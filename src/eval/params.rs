@@ -0,0 +1,116 @@
+use std::fs;
+
+use super::{psqt::{BISHOP_EG, BISHOP_MG, KING_EG, KING_MG, KNIGHT_EG, KNIGHT_MG, PAWN_EG, PAWN_MG, QUEEN_EG, QUEEN_MG, ROOK_EG, ROOK_MG}, BISHOP, KNIGHT, MOBILITY, PAWN, QUEEN, ROOK};
+
+// Classical eval weights, loadable at runtime so tuner output can be tried without a rebuild
+#[derive(Clone, Debug)]
+pub struct EvalParams {
+    // Pawn to queen, kings aren't counted
+    pub material: [i32; 5],
+    pub mobility: i32,
+    // [piece][square] as laid out in `psqt`, which is black's view; white reads them flipped
+    pub psqt_mg: [[i32; 64]; 6],
+    pub psqt_eg: [[i32; 64]; 6]
+}
+
+const PIECE_NAMES: [&str; 6] = [ "pawn", "knight", "bishop", "rook", "queen", "king" ];
+
+pub fn create_eval_params() -> EvalParams {
+    EvalParams {
+        material: [ PAWN, KNIGHT, BISHOP, ROOK, QUEEN ],
+        mobility: MOBILITY,
+        psqt_mg: [ PAWN_MG, KNIGHT_MG, BISHOP_MG, ROOK_MG, QUEEN_MG, KING_MG ],
+        psqt_eg: [ PAWN_EG, KNIGHT_EG, BISHOP_EG, ROOK_EG, QUEEN_EG, KING_EG ]
+    }
+}
+
+fn display_table(values: &[i32]) -> String {
+    let rows: Vec<String> = values.chunks(8)
+        .map(|row| row.iter().map(|value| format!("{:>5}", value)).collect::<Vec<_>>().join(","))
+        .collect();
+
+    format!("[\n{}\n]", rows.join(",\n"))
+}
+
+// Written as TOML: plain integers, and arrays that may span lines
+pub fn export_params(path: &str, params: &EvalParams) -> Result<(), String> {
+    let mut lines = vec![
+        "# artifact eval params".to_string(),
+        format!("material = [ {} ]", params.material.map(|value| value.to_string()).join(", ")),
+        format!("mobility = {}", params.mobility)
+    ];
+
+    for (piece, name) in PIECE_NAMES.iter().enumerate() {
+        lines.push(format!("{}_mg = {}", name, display_table(&params.psqt_mg[piece])));
+        lines.push(format!("{}_eg = {}", name, display_table(&params.psqt_eg[piece])));
+    }
+
+    lines.push(String::new());
+    fs::write(path, lines.join("\n")).map_err(|error| format!("failed to write {}: {}", path, error))
+}
+
+fn parse_values(path: &str, key: &str, value: &str, expected: usize) -> Result<Vec<i32>, String> {
+    let values: Result<Vec<i32>, _> = value.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::parse)
+        .collect();
+
+    match values {
+        Ok(values) if values.len() == expected => Ok(values),
+        Ok(values) => Err(format!("{}: {} has {} values, expected {}", path, key, values.len(), expected)),
+        Err(_) => Err(format!("{}: {} has a value that isn't an integer", path, key))
+    }
+}
+
+// Keys missing from the file keep their built-in values
+pub fn import_params(path: &str) -> Result<EvalParams, String> {
+    let contents = fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let mut params = create_eval_params();
+
+    // Join arrays spread over several lines back into one `key = value` entry
+    let mut entries: Vec<(String, String)> = vec![];
+    let mut open = false;
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match entries.last_mut() {
+            Some((_, value)) if open => {
+                value.push(',');
+                value.push_str(line);
+            }
+            _ => match line.split_once('=') {
+                Some((key, value)) => entries.push((key.trim().to_string(), value.trim().to_string())),
+                None => return Err(format!("{}: malformed line {}", path, line))
+            }
+        }
+
+        open = entries.last().is_some_and(|(_, value)| value.starts_with('[') && !value.ends_with(']'));
+    }
+
+    for (key, value) in entries {
+        match key.as_str() {
+            "material" => params.material.copy_from_slice(&parse_values(path, &key, &value, 5)?),
+            "mobility" => params.mobility = parse_values(path, &key, &value, 1)?[0],
+            _ => {
+                let (name, phase) = key.rsplit_once('_').ok_or_else(|| format!("{}: unknown key {}", path, key))?;
+                let piece = PIECE_NAMES.iter().position(|&piece| piece == name);
+
+                let table = match (piece, phase) {
+                    (Some(piece), "mg") => &mut params.psqt_mg[piece],
+                    (Some(piece), "eg") => &mut params.psqt_eg[piece],
+                    _ => return Err(format!("{}: unknown key {}", path, key))
+                };
+                table.copy_from_slice(&parse_values(path, &key, &value, 64)?);
+            }
+        }
+    }
+
+    Ok(params)
+}
@@ -137,27 +137,3 @@ pub const KING_EG: [i32; 64] = [
 pub const fn flip_sq(sq: u8) -> u8 {
     sq ^ 56
 }
-
-pub const fn flip(table: [i32; 64]) -> [i32; 64] {
-    let mut flipped = [0; 64];
-    let mut i = 0;
-    while i < 64 {
-        flipped[flip_sq(i as u8) as usize] = table[i];
-        i += 1;
-    }
-    flipped
-}
-
-pub const PAWN_MG_WHITE: [i32; 64] = flip(PAWN_MG);
-pub const KNIGHT_MG_WHITE: [i32; 64] = flip(KNIGHT_MG);
-pub const BISHOP_MG_WHITE: [i32; 64] = flip(BISHOP_MG);
-pub const ROOK_MG_WHITE: [i32; 64] = flip(ROOK_MG);
-pub const QUEEN_MG_WHITE: [i32; 64] = flip(QUEEN_MG);
-pub const KING_MG_WHITE: [i32; 64] = flip(KING_MG);
-
-pub const PAWN_EG_WHITE: [i32; 64] = flip(PAWN_EG);
-pub const KNIGHT_EG_WHITE: [i32; 64] = flip(KNIGHT_EG);
-pub const BISHOP_EG_WHITE: [i32; 64] = flip(BISHOP_EG);
-pub const ROOK_EG_WHITE: [i32; 64] = flip(ROOK_EG);
-pub const QUEEN_EG_WHITE: [i32; 64] = flip(QUEEN_EG);
-pub const KING_EG_WHITE: [i32; 64] = flip(KING_EG);
\ No newline at end of file
@@ -0,0 +1,187 @@
+use chessing::{bitboard::BitInt, game::{action::Action, Board, Team}};
+
+use crate::san::{piece_char, square_name};
+
+// The piece on every square, using FEN letters
+pub fn square_chars<T: BitInt, const N: usize>(board: &Board<T, N>) -> Vec<Option<char>> {
+    let squares = (board.game.bounds.rows * board.game.bounds.cols) as usize;
+
+    let mut white = vec![ false; squares ];
+    for sq in board.state.white.iter() {
+        white[sq as usize] = true;
+    }
+
+    let mut chars = vec![ None; squares ];
+    for (piece, pieces) in board.state.pieces.iter().enumerate() {
+        for sq in pieces.iter() {
+            let sq = sq as usize;
+            let team = if white[sq] { Team::White } else { Team::Black };
+            chars[sq] = Some(piece_char(piece, team));
+        }
+    }
+
+    chars
+}
+
+pub fn placement<T: BitInt, const N: usize>(board: &Board<T, N>) -> String {
+    let rows = board.game.bounds.rows as usize;
+    let cols = board.game.bounds.cols as usize;
+    let chars = square_chars(board);
+
+    let mut ranks = vec![];
+    for row in (0..rows).rev() {
+        let mut rank = String::new();
+        let mut empty = 0;
+
+        for col in 0..cols {
+            match chars[row * cols + col] {
+                Some(piece) => {
+                    if empty > 0 {
+                        rank.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    rank.push(piece);
+                }
+                None => empty += 1
+            }
+        }
+
+        if empty > 0 {
+            rank.push_str(&empty.to_string());
+        }
+
+        ranks.push(rank);
+    }
+
+    ranks.join("/")
+}
+
+// Enough for trainers, which only care about the pieces and side to move
+pub fn to_fen<T: BitInt, const N: usize>(board: &Board<T, N>, fullmove: usize) -> String {
+    let team = match board.state.moving_team {
+        Team::White => 'w',
+        Team::Black => 'b'
+    };
+
+    format!("{} {} - - 0 {}", placement(board), team, fullmove)
+}
+
+// (square, rights lost when a move leaves or lands on it) for standard chess
+const CASTLING_SQUARES: [(usize, &str); 6] = [ (4, "KQ"), (7, "K"), (0, "Q"), (60, "kq"), (63, "k"), (56, "q") ];
+
+// Castling rights, en passant and the clocks can't be read off the board, so they're followed
+// from the FEN `position` started with through the moves played since
+pub fn export_fen<T: BitInt, const N: usize>(board: &Board<T, N>, start: Option<&str>, played: &[Action], halfmove: usize) -> String {
+    let fields: Vec<&str> = start.map(|fen| fen.split_whitespace().collect()).unwrap_or_default();
+    let starts_black = fields.get(1) == Some(&"b");
+    let start_fullmove: usize = fields.get(5).and_then(|fullmove| fullmove.parse().ok()).unwrap_or(1);
+
+    let mut castling: Vec<char> = fields.get(2).copied().unwrap_or("KQkq").chars().filter(|&c| c != '-').collect();
+    for action in played {
+        for (sq, lost) in CASTLING_SQUARES {
+            if action.from as usize == sq || action.to as usize == sq {
+                castling.retain(|&right| !lost.contains(right));
+            }
+        }
+    }
+    let castling: String = if castling.is_empty() { "-".to_string() } else { castling.into_iter().collect() };
+
+    // Listed after any double pawn push, whether or not a capture is possible
+    let cols = board.game.bounds.cols as usize;
+    let en_passant = match played.last() {
+        Some(action) if action.piece as usize == 0 && (action.from as usize).abs_diff(action.to as usize) == 2 * cols => {
+            square_name(board, (action.from as usize + action.to as usize) / 2)
+        }
+        Some(_) => "-".to_string(),
+        None => fields.get(3).copied().unwrap_or("-").to_string()
+    };
+
+    let team = match board.state.moving_team {
+        Team::White => 'w',
+        Team::Black => 'b'
+    };
+    let fullmove = start_fullmove + (played.len() + starts_black as usize) / 2;
+
+    format!("{} {} {} {} {} {}", placement(board), team, castling, en_passant, halfmove, fullmove)
+}
+
+// The same position with the colours swapped and the board turned upside down, which a
+// symmetric eval must score the same from the side to move
+pub fn mirror_fen<T: BitInt, const N: usize>(board: &Board<T, N>, fen: &str) -> String {
+    let rows = board.game.bounds.rows as usize;
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let swap_case = |c: char| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() };
+
+    let placement: Vec<String> = fields[0].split('/').rev().map(|rank| rank.chars().map(swap_case).collect()).collect();
+    let team = if fields[1] == "w" { "b" } else { "w" };
+    let castling: String = fields[2].chars().map(swap_case).collect();
+    let (file, rank) = fields[3].split_at(1);
+    let en_passant = match rank.parse::<usize>() {
+        Ok(rank) => format!("{}{}", file, rows + 1 - rank),
+        Err(_) => "-".to_string()
+    };
+
+    format!("{} {} {} {} {} {}", placement.join("/"), team, castling, en_passant, fields[4], fields[5])
+}
+
+// Hands the move to the other side, as though the side to move passed; en passant is dropped
+// since only the side that just moved could have been given it
+pub fn flip_fen(fen: &str) -> String {
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    fields[1] = if fields[1] == "w" { "b" } else { "w" };
+    fields[3] = "-";
+
+    fields.join(" ")
+}
+
+// Catches what would make `load` panic, so a bad `position fen` is refused rather than taking
+// the engine down; chess letters only, as that's the only game `position` sets up
+pub fn check_fen<T: BitInt, const N: usize>(board: &Board<T, N>, fen: &str) -> Result<(), String> {
+    let rows = board.game.bounds.rows as usize;
+    let cols = board.game.bounds.cols as usize;
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let bad = |why: &str| Err(format!("invalid fen {}: {}", fen, why));
+
+    let ranks: Vec<&str> = match fields.first() {
+        Some(placement) => placement.split('/').collect(),
+        None => return bad("it's empty")
+    };
+    if ranks.len() != rows {
+        return bad("wrong number of ranks");
+    }
+
+    for rank in &ranks {
+        let mut squares = 0;
+        for c in rank.chars() {
+            match c {
+                '1'..='9' => squares += c as usize - '0' as usize,
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => squares += 1,
+                _ => return bad("unknown piece")
+            }
+        }
+
+        if squares != cols {
+            return bad("a rank has the wrong number of squares");
+        }
+    }
+
+    let placement = fields[0];
+    if placement.matches('K').count() != 1 || placement.matches('k').count() != 1 {
+        return bad("each side needs exactly one king");
+    }
+
+    if !matches!(fields.get(1), Some(&"w") | Some(&"b")) {
+        return bad("side to move isn't w or b");
+    }
+
+    if fields.get(2).is_some_and(|castling| !castling.chars().all(|c| "KQkq-".contains(c))) {
+        return bad("bad castling rights");
+    }
+
+    let numbers_ok = fields.iter().skip(4).take(2).all(|field| field.parse::<u32>().is_ok());
+    if !numbers_ok {
+        return bad("clocks aren't numbers");
+    }
+
+    Ok(())
+}
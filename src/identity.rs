@@ -0,0 +1,68 @@
+use crate::{options::{OptionKind, ENGINE_OPTIONS}, variants::VARIANTS};
+
+pub const ENGINE_NAME: &str = "Artifact";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub fn full_name() -> String {
+    format!("{} {}", ENGINE_NAME, VERSION)
+}
+
+// What this build can do, so testers can tell binaries apart from `uci` alone
+pub struct Capabilities {
+    pub variants: Vec<&'static str>,
+    pub embedded_net: bool,
+    // Whether a network is in use right now, embedded or loaded through EvalFile
+    pub nnue: bool,
+    pub syzygy: bool,
+    pub max_threads: usize
+}
+
+// As many as the `Threads` option accepts
+fn max_threads() -> usize {
+    ENGINE_OPTIONS.iter()
+        .find_map(|option| match option.kind {
+            OptionKind::Spin { max, .. } if option.name == "Threads" => Some(max as usize),
+            _ => None
+        })
+        .unwrap_or(1)
+}
+
+pub fn capabilities(nnue: bool) -> Capabilities {
+    Capabilities {
+        variants: VARIANTS.iter().map(|variant| variant.name).collect(),
+        embedded_net: cfg!(feature = "embedded-net"),
+        nnue,
+        syzygy: false,
+        max_threads: max_threads()
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+// Sent in response to `uci`, before the options
+pub fn identity_lines(capabilities: &Capabilities) -> Vec<String> {
+    vec![
+        format!("id name {}", full_name()),
+        format!("info string variants {}", capabilities.variants.join(" ")),
+        format!(
+            "info string features nnue {} embedded-net {} syzygy {} threads {}",
+            yes_no(capabilities.nnue), yes_no(capabilities.embedded_net),
+            yes_no(capabilities.syzygy), capabilities.max_threads
+        )
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features_report_the_threads_option_maximum() {
+        let lines = identity_lines(&capabilities(false));
+
+        assert_eq!(capabilities(false).max_threads, 256);
+        assert!(lines[2].ends_with("threads 256"), "{}", lines[2]);
+    }
+}
@@ -0,0 +1,117 @@
+use std::{io, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, mpsc::{self, Receiver}}, thread};
+
+use crate::options::{parse_setoption, validate_option, OptionValue};
+
+// Answer `isready` during searches and keep quiet about unknown commands (`StrictUCI`)
+static STRICT: AtomicBool = AtomicBool::new(false);
+static SEARCHING: AtomicBool = AtomicBool::new(false);
+// A MultiPV sent mid-search, which restarts it; 0 if there's none
+static PENDING_MULTIPV: AtomicUsize = AtomicUsize::new(0);
+// `go` commands read so far, and how many had been read when the last `stop` or `quit` was.
+// Matched against the searches started, so a `stop` sent before its search has even begun
+// still ends it, and one sent while idle doesn't end the next
+static GOS_READ: AtomicUsize = AtomicUsize::new(0);
+static STOPPED_GO: AtomicUsize = AtomicUsize::new(0);
+static GOS_STARTED: AtomicUsize = AtomicUsize::new(0);
+// Lines passed on to the main loop, and how many it had handled when it began thinking on the
+// opponent's time; any line past that ends the thinking
+static LINES_SENT: AtomicUsize = AtomicUsize::new(0);
+static THINKING_UNTIL: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::SeqCst);
+}
+
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::SeqCst)
+}
+
+// Stdin is read on its own thread so `isready` can be answered while the main thread searches;
+// everything else waits in the channel, so a `position` or `go` sent mid-search runs once it's done
+pub fn spawn_reader() -> Receiver<io::Result<String>> {
+    let (sender, lines) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            if let Ok(command) = &line {
+                if command.trim() == "isready" && is_strict() && SEARCHING.load(Ordering::SeqCst) {
+                    println!("readyok");
+                    continue;
+                }
+
+                // Still passed on, so `quit` exits once the search has sent its best move
+                match command.split_whitespace().next() {
+                    Some("go") => { GOS_READ.fetch_add(1, Ordering::SeqCst); }
+                    Some("stop") | Some("quit") => STOPPED_GO.store(GOS_READ.load(Ordering::SeqCst), Ordering::SeqCst),
+                    _ => {}
+                }
+
+                // Still passed on, so the main loop's copy of the option agrees once the search ends
+                if SEARCHING.load(Ordering::SeqCst) {
+                    let args: Vec<&str> = command.split_whitespace().collect();
+                    let option = parse_setoption(&args).and_then(|(name, value)| validate_option(&name, &value).ok());
+
+                    if let Some(("MultiPV", OptionValue::Spin(multipv))) = option {
+                        PENDING_MULTIPV.store(multipv as usize, Ordering::SeqCst);
+                    }
+                }
+            }
+
+            LINES_SENT.fetch_add(1, Ordering::SeqCst);
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    lines
+}
+
+// Marks a search as running for as long as it's alive; taken once for every `go`
+pub struct Searching;
+
+pub fn searching() -> Searching {
+    GOS_STARTED.fetch_add(1, Ordering::SeqCst);
+    SEARCHING.store(true, Ordering::SeqCst);
+    Searching
+}
+
+impl Drop for Searching {
+    fn drop(&mut self) {
+        SEARCHING.store(false, Ordering::SeqCst);
+    }
+}
+
+pub fn stop_requested() -> bool {
+    let stopped = SEARCHING.load(Ordering::Relaxed) && STOPPED_GO.load(Ordering::Relaxed) == GOS_STARTED.load(Ordering::Relaxed);
+    stopped || LINES_SENT.load(Ordering::Relaxed) > THINKING_UNTIL.load(Ordering::Relaxed)
+}
+
+// Marks thinking on the opponent's time (`PermanentBrain`) for as long as it's alive; only for
+// input from the reader thread, as nothing else could interrupt it
+pub struct ThinkingAhead;
+
+pub fn thinking_ahead(lines_handled: usize) -> ThinkingAhead {
+    THINKING_UNTIL.store(lines_handled, Ordering::SeqCst);
+    ThinkingAhead
+}
+
+impl Drop for ThinkingAhead {
+    fn drop(&mut self) {
+        THINKING_UNTIL.store(usize::MAX, Ordering::SeqCst);
+    }
+}
+
+pub fn pending_multipv() -> Option<usize> {
+    match PENDING_MULTIPV.load(Ordering::SeqCst) {
+        0 => None,
+        multipv => Some(multipv)
+    }
+}
+
+pub fn take_pending_multipv() -> Option<usize> {
+    match PENDING_MULTIPV.swap(0, Ordering::SeqCst) {
+        0 => None,
+        multipv => Some(multipv)
+    }
+}
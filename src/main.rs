@@ -1,108 +1,637 @@
-use std::{io, process, thread, time::Duration};
+use std::{env, io, process, sync::Arc, thread, time::Duration};
 
-use chessing::{chess::Chess, game::{GameTemplate, Team}, uci::{parse::{GoOption, UciCommand, UciPosition}, respond::Info, Uci}};
-use search::{create_search_info, iterative_deepening, search, SearchInfo};
+use chessing::{chess::Chess, game::{action::Action, Board, GameTemplate}, uci::{parse::{UciCommand, UciPosition}, respond::Info, Uci}};
+use eval::{find_ablation, params::{export_params, import_params}};
+use nnue::{create_nnue_state, embedded_network, load_network};
+use san::parse_uci;
+use fen::{check_fen, export_fen, flip_fen, mirror_fen};
+use personality::{apply_personality, find_personality, PERSONALITIES};
+use identity::{capabilities, identity_lines};
+use profiles::{load_profiles, parse_opponent, select_profile, Opponent, DEFAULT_PROFILE};
+use util::current_time_millis;
+use variants::find_variant;
+use session::{apply_session, load_session, save_session};
+use options::{display_option, parse_setoption, uci_options, validate_option, OptionValue};
+use search::{apply_limits, create_search_info, in_check, iterative_deepening, legal_actions, resets_halfmove, params::{set_param, spsa_inputs}, search, quiet::{is_quiet, resolve_to_quiet}, stats::stats_report, limits::{max_depth, parse_limits, Limits}, time::{book_exit_time, classify_root, clock_times, is_endgame, scale_endgame_time, scale_soft_time, FORCED_MOVE_DEPTH, FORCED_MOVE_TIME}, search_to_depth, stop_reason_name, tt::{create_tt, find_replacement, tt_entries}, SearchInfo, MAX_DEPTH, SEARCH_STACK};
 
 mod search;
 mod util;
 mod eval;
+mod bench;
+mod variants;
+mod play;
+mod san;
+mod nnue;
+mod fen;
+mod datagen;
+mod runner;
+mod options;
+mod signals;
+mod identity;
+mod session;
+mod profiles;
+mod perft;
+mod roundtrip;
+mod personality;
+mod input;
 
+// Plays `position` moves onto a freshly loaded board, recording repetition hashes and the halfmove clock;
+// returns the moves as actions, stopping short at the first one that isn't legal
+fn play_moves(board: &mut Board<u64, 6>, info: &mut SearchInfo, fen: Option<&str>, moves: &[String]) -> Vec<Action> {
+    let mut halfmove = fen
+        .and_then(|fen| fen.split_whitespace().nth(4))
+        .and_then(|clock| clock.parse().ok())
+        .unwrap_or(0);
+
+    info.hashes = vec![ board.game.rules.hash(board, &info.zobrist) ];
+    let mut played = vec![];
+
+    for act in moves {
+        // Played as the action the name was parsed to, so it's the same move the search chose
+        match parse_uci(board, act) {
+            Some(action) => {
+                halfmove = if resets_halfmove(board, info, action) { 0 } else { halfmove + 1 };
+                played.push(action);
+                board.play(action);
+                info.hashes.push(board.game.rules.hash(board, &info.zobrist));
+            }
+            None => {
+                println!("info string illegal move {}, ignoring the rest of the line", act);
+                break;
+            }
+        }
+    }
+
+    info.root_halfmove = halfmove;
+    played
+}
+
+// Searches the position after `best` and the expected `reply` without output until the next command
+// is read, so the TT is already filled if the reply is the one played
+fn think_ahead(board: &mut Board<u64, 6>, info: &mut SearchInfo, best: Action, reply: Action, lines_handled: usize) {
+    let root_halfmove = info.root_halfmove;
+    let mut states = vec![];
+
+    for action in [best, reply] {
+        info.root_halfmove = if resets_halfmove(board, info, action) { 0 } else { info.root_halfmove + 1 };
+        states.push(board.play(action));
+        info.hashes.push(board.game.rules.hash(board, &info.zobrist));
+    }
+
+    // Thinking ahead is unbounded whatever the last `go` asked for
+    apply_limits(info, board, &Limits::default());
+
+    let thinking = input::thinking_ahead(lines_handled);
+    search_to_depth(info, board, MAX_DEPTH, u64::MAX, u64::MAX);
+    drop(thinking);
+
+    while let Some(state) = states.pop() {
+        board.restore(state);
+        info.hashes.pop();
+    }
+    info.root_halfmove = root_halfmove;
+}
+
+// The search recurses on whichever thread runs the UCI loop, so it gets a stack sized for that
 fn main() {
+    match thread::Builder::new().stack_size(SEARCH_STACK).spawn(run) {
+        Ok(handle) => {
+            if handle.join().is_err() {
+                process::exit(101);
+            }
+        }
+        Err(error) => {
+            println!("info string failed to start the search thread, running on the main one: {}", error);
+            run();
+        }
+    }
+}
+
+fn run() {
+    signals::install_handlers();
+
     let uci = Uci { log: true };
-    let stdin = io::stdin();
 
     let chess = Chess::create::<u64, 6>();
     let mut board = chess.default();
 
+    let default_network = match embedded_network() {
+        Some(Ok(network)) => Some(Arc::new(network)),
+        Some(Err(error)) => {
+            println!("info string embedded network is invalid, using the classical eval: {}", error);
+            None
+        }
+        None => None
+    };
+    let mut network = default_network.clone();
+
     let mut info = create_search_info(&mut board);
+    info.nnue = network.clone().map(create_nnue_state);
+
+    // `artifact "position startpos" "go depth 20"` runs each argument as a command and exits,
+    // except `artifact bench 12`, where the arguments form a single command
+    let argv: Vec<String> = env::args().skip(1).collect();
+    // 0 leaves the move time to the clock
+    let mut max_move_time: u64 = 0;
+
+    let mut profile_rules = vec![];
+    let mut opponent = Opponent::default();
+    let mut profile = DEFAULT_PROFILE;
+    let mut timeman_report = false;
+    // Imported eval weights before the personality tilts them
+    let mut base_eval_params = info.eval_params.clone();
+    let mut personality = PERSONALITIES[0];
+    let mut contempt = 0;
+
+    // The last `position`, kept for `save session`
+    let mut current_fen: Option<String> = None;
+    let mut current_moves: Vec<String> = vec![];
+    let mut current_actions = vec![];
 
-    for line in stdin.lines() {
-        let line = line.expect("Line is set");
+    // Thinking on the opponent's time needs the reader thread to notice the next command
+    let interactive = argv.is_empty();
+    let mut permanent_brain = false;
+    let mut lines_handled = 0;
+
+    let mut lines: Box<dyn Iterator<Item = io::Result<String>>> = match argv.first().map(|arg| arg.as_str()) {
+        None => Box::new(input::spawn_reader().into_iter()),
+        Some("bench") => Box::new(std::iter::once(Ok(argv.join(" ")))),
+        Some(_) => Box::new(argv.into_iter().map(Ok))
+    };
+
+    while let Some(line) = lines.next() {
+        lines_handled += 1;
+
+        // Input that isn't UTF-8 is dropped rather than taking the engine down
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => continue
+        };
+
+        // Set when a signal arrived during work that wound itself down first
+        if signals::shutdown_requested() {
+            process::exit(signals::exit_code());
+        }
 
         match uci.parse(&line) {
             UciCommand::Uci() => {
+                for line in identity_lines(&capabilities(network.is_some())) {
+                    println!("{}", line);
+                }
+
+                for option in uci_options() {
+                    println!("{}", display_option(&option));
+                }
+
                 uci.uciok();
             }
-            UciCommand::Go { options } => {
-                let mut soft_time = 0;
-                let mut hard_time = 0;
-                let team = board.state.moving_team;
-                
-                for option in options {
-                    match option {
-                        GoOption::BTime(time) => {
-                            if team == Team::Black {
-                                soft_time += time / 40;
-                                hard_time += time / 9;
-                            }
-                        }
-                        GoOption::BInc(inc) => {
-                            soft_time += inc / 4;
-                        }
-                        GoOption::WTime(time) => {
-                            if team == Team::White {
-                                soft_time += time / 40;
-                                hard_time += time / 9;
-                            }
-                        }
-                        GoOption::WInc(inc) => {
-                            if team == Team::White {
-                                soft_time += inc / 4;
-                            }
-                        }
-                        GoOption::MoveTime(time) => {
-                            soft_time += time / 2;
-                            hard_time += time;
-                        }
-                        _ => {}
-                    }
+            UciCommand::Go { .. } => {
+                // Before anything that can skip the search, so every `go` is counted
+                let searching = input::searching();
+
+                // `position` may have played into checkmate or stalemate, there's nothing to search
+                let root_actions = legal_actions(&mut board);
+                if root_actions.is_empty() {
+                    let reason = if in_check(&mut board) { "checkmate" } else { "stalemate" };
+                    println!("info string position is {}, no legal moves", reason);
+                    uci.bestmove("0000");
+                    continue;
                 }
 
-                if soft_time == 0 {
-                    soft_time = 300;
+                let mut limits = parse_limits(&line);
+                let (mut soft_time, mut hard_time, on_clock) = clock_times(&limits, board.state.moving_team);
+                let infinite = limits.infinite;
+
+                // Tactical positions get more of the clock, dead-equal quiet ones less, endgames more again
+                info.endgame = on_clock && !infinite && is_endgame(&board, &info);
+                if on_clock && !infinite {
+                    let class = classify_root(&mut board, &mut info);
+                    let scaled = scale_endgame_time(&info, scale_soft_time(soft_time, class));
+                    soft_time = book_exit_time(&info, scaled * profile.time_percent / 100).min(hard_time);
+                }
+
+                // A forced move only gets a quick look, enough to report a score
+                if root_actions.len() == 1 && on_clock && !infinite {
+                    limits.depth = Some(max_depth(&limits).min(FORCED_MOVE_DEPTH));
+                    soft_time = soft_time.min(FORCED_MOVE_TIME);
+                }
+
+                // Without a clock for the side to move there's nothing to abort against
+                if hard_time == 0 {
+                    hard_time = u64::MAX;
                 }
 
-                iterative_deepening(&uci, &mut info, &mut board, soft_time, hard_time);
+                // MaxMoveTime caps the move no matter what the clock allows
+                if max_move_time > 0 {
+                    hard_time = hard_time.min(max_move_time);
+                    soft_time = soft_time.min(max_move_time);
+                }
+
+                let busy = signals::busy();
+                let result = iterative_deepening(&mut info, &mut board, &limits, soft_time, hard_time);
+                drop(searching);
+
+                // A signal can stop the search before the first iteration finishes
+                if timeman_report {
+                    let limit = |time: u64| if time == u64::MAX { "none".to_string() } else { time.to_string() };
+                    let (used, iterations, stop) = match &result {
+                        Some(result) => (result.time, result.depth, stop_reason_name(result.stop)),
+                        None => (0, 0, stop_reason_name(info.stop))
+                    };
+
+                    println!(
+                        "info string timeman soft {} hard {} used {} iterations {} stop {}",
+                        limit(soft_time), limit(hard_time), used, iterations, stop
+                    );
+                }
 
-                let action = info.best_move.expect("There's a best move, right?");
-                let action_display = board.display_uci_action(action);
+                let expected = result.as_ref().and_then(|result| result.ponder.map(|reply| (result.best, reply)));
+                match result {
+                    Some(result) => uci.bestmove(&board.display_uci_action(result.best)),
+                    None => uci.bestmove("0000")
+                }
 
-                uci.bestmove(&action_display);
+                drop(busy);
+                if signals::shutdown_requested() {
+                    process::exit(signals::exit_code());
+                }
 
-                info.best_move = None;
+                // For bots that can't drive `go ponder`, see `think_ahead`
+                if let Some((best, reply)) = expected.filter(|_| permanent_brain && interactive) {
+                    think_ahead(&mut board, &mut info, best, reply, lines_handled);
+                }
             }
             UciCommand::IsReady() => {
                 uci.readyok();
             }
             UciCommand::Position { position, moves } => {
-                match position {
-                    UciPosition::Fen(fen) => {
-                        board = chess.load(&fen);
-                    } 
-                    UciPosition::Startpos => {
-                        board = chess.default();
-                    }
+                let fen = match position {
+                    UciPosition::Fen(fen) => Some(fen),
+                    UciPosition::Startpos => None
+                };
+
+                // A bad FEN leaves the last position in place
+                if let Some(Err(error)) = fen.as_deref().map(|fen| check_fen(&board, fen)) {
+                    println!("info string {}", error);
+                    continue;
                 }
 
-                info.hashes = vec![];
+                current_fen = fen;
+                current_moves = moves;
 
-                for act in moves {
-                    info.hashes.push(chess.rules.hash(&mut board, &info.zobrist));
-                    board.play_action(&act);
-                }
+                board = match &current_fen {
+                    Some(fen) => chess.load(fen),
+                    None => chess.default()
+                };
+                current_actions = play_moves(&mut board, &mut info, current_fen.as_deref(), &current_moves);
+                current_moves.truncate(current_actions.len());
             }
             UciCommand::Quit() => {
                 process::exit(0x100);
             }
-            UciCommand::Stop() => {
-                // TODO
-            }
+            // Searches are stopped from the input thread, see `input::stop_requested`; by the time
+            // the main loop reads `stop` there's nothing left running
+            UciCommand::Stop() => {}
             UciCommand::UciNewGame() => {
                 // TODO
+                let san_output = info.san_output;
+                let kibitz = info.kibitz;
+                let root_noise = (info.root_noise, info.root_noise_plies, info.root_noise_seed);
+                let (multipv, show_wdl) = (info.multipv, info.show_wdl);
+                let (tt_replacement, threads) = (info.tt_replacement, info.threads);
+                let tt = info.tt.clone();
+                let params = info.params.clone();
+                let eval_params = info.eval_params.clone();
+                let ablated = info.ablated.clone();
                 info = create_search_info(&mut board);
+                info.san_output = san_output;
+                info.kibitz = kibitz;
+                (info.root_noise, info.root_noise_plies, info.root_noise_seed) = root_noise;
+                (info.multipv, info.show_wdl) = (multipv, show_wdl);
+                (info.tt_replacement, info.threads) = (tt_replacement, threads);
+                // Keeps the size `Hash` asked for
+                tt.clear();
+                info.tt = tt;
+                info.contempt = contempt + profile.contempt + personality.contempt;
+                info.params = params;
+                info.eval_params = eval_params;
+                info.ablated = ablated;
+                info.nnue = network.clone().map(create_nnue_state);
             }
-            UciCommand::Unknown(cmd) => {
-                // TODO
+            UciCommand::Unknown(_) => {
+                let args: Vec<&str> = line.split_whitespace().collect();
+
+                match args.as_slice() {
+                    ["bench", "variants"] => {
+                        bench::bench_variants(bench::BENCH_DEPTH);
+                    }
+                    ["bench", "variants", depth] => {
+                        bench::bench_variants(depth.parse().unwrap_or(bench::BENCH_DEPTH));
+                    }
+                    ["scaling"] => {
+                        bench::scaling(&bench::SCALING_NODES);
+                    }
+                    ["scaling", limits @ ..] => {
+                        let limits: Vec<u64> = limits.iter().filter_map(|limit| limit.parse().ok()).collect();
+                        bench::scaling(&limits);
+                    }
+                    // Movegen regression check against known perft counts
+                    ["perft", depth] | ["divide", depth] => {
+                        let depth = depth.parse().unwrap_or(1);
+                        let start = current_time_millis();
+
+                        let nodes = if args[0] == "divide" {
+                            let divided = perft::perft_divide(&mut board, depth);
+                            for (name, nodes) in &divided {
+                                println!("{}: {}", name, nodes);
+                            }
+                            divided.iter().map(|(_, nodes)| nodes).sum()
+                        } else {
+                            perft::perft(&mut board, depth)
+                        };
+
+                        let time = current_time_millis() - start;
+                        println!("{} nodes {} nps", nodes, bench::nps(nodes, time));
+                    }
+                    // `movegen [seconds]`
+                    ["movegen", options @ ..] => {
+                        let seconds = options.first().and_then(|seconds| seconds.parse().ok()).unwrap_or(perft::MOVEGEN_SECONDS);
+                        println!("{} nps", perft::movegen_bench(&mut board, seconds));
+                    }
+                    ["validate"] | ["validate", _] => {
+                        let name = args.get(1).copied().unwrap_or("chess");
+                        match find_variant(name) {
+                            Some(variant) => {
+                                perft::validate(variant);
+                            }
+                            None => println!("info string unknown variant {}", name)
+                        }
+                    }
+                    // Random games checking every legal move survives being named and parsed back
+                    ["roundtrip", options @ ..] => {
+                        let games = options.first().and_then(|games| games.parse().ok()).unwrap_or(roundtrip::ROUNDTRIP_GAMES);
+                        let seed = options.get(1).and_then(|seed| seed.parse().ok()).unwrap_or(1);
+                        roundtrip::roundtrip(games, seed);
+                    }
+                    ["bench"] => {
+                        bench::bench(bench::BENCH_DEPTH, &info.ablated);
+                    }
+                    // `bench tt [depth] [entries]`
+                    ["bench", "tt", options @ ..] => {
+                        let depth = options.first().and_then(|depth| depth.parse().ok()).unwrap_or(bench::BENCH_DEPTH);
+                        let entries = options.get(1).and_then(|entries| entries.parse().ok()).unwrap_or(bench::TT_BENCH_ENTRIES);
+                        bench::bench_tt(depth, entries);
+                    }
+                    ["bench", depth] => {
+                        bench::bench(depth.parse().unwrap_or(bench::BENCH_DEPTH), &info.ablated);
+                    }
+                    ["play", options @ ..] => {
+                        play::play(&mut lines, options);
+                    }
+                    ["datagen", options @ ..] => {
+                        let busy = signals::busy();
+                        datagen::datagen(options);
+
+                        drop(busy);
+                        if signals::shutdown_requested() {
+                            process::exit(signals::exit_code());
+                        }
+                    }
+                    ["match", options @ ..] => {
+                        let busy = signals::busy();
+                        runner::run_match(options);
+
+                        drop(busy);
+                        if signals::shutdown_requested() {
+                            process::exit(signals::exit_code());
+                        }
+                    }
+                    // Protocol checks against an engine binary, this one if none is given
+                    ["conformance", engine @ ..] => {
+                        runner::conformance(engine);
+                    }
+                    // `fuzz [commands] [seed] [engine]`
+                    ["fuzz", options @ ..] => {
+                        let commands = options.first().and_then(|commands| commands.parse().ok()).unwrap_or(runner::FUZZ_COMMANDS);
+                        let seed = options.get(1).and_then(|seed| seed.parse().ok()).unwrap_or(1);
+                        runner::fuzz(commands, seed, options.get(2..).unwrap_or(&[]));
+                    }
+                    // `save session <file> tt` also writes the transposition table
+                    ["save", "session", path] | ["save", "session", path, "tt"] => {
+                        let include_tt = args.len() == 4;
+                        match save_session(path, &mut board, &info, current_fen.as_deref(), &current_moves, include_tt) {
+                            Ok(()) => println!("info string saved session to {}", path),
+                            Err(error) => println!("info string {}", error)
+                        }
+                    }
+                    ["load", "session", path] => {
+                        match load_session(path) {
+                            Ok(session) if session.fen.as_deref().is_some_and(|fen| check_fen(&board, fen).is_err()) => {
+                                println!("info string {} holds an invalid fen", path);
+                            }
+                            Ok(session) => {
+                                board = match &session.fen {
+                                    Some(fen) => chess.load(fen),
+                                    None => chess.default()
+                                };
+                                current_actions = play_moves(&mut board, &mut info, session.fen.as_deref(), &session.moves);
+                                apply_session(&mut board, &mut info, &session);
+
+                                current_fen = session.fen;
+                                current_moves = session.moves;
+                                current_moves.truncate(current_actions.len());
+                                println!("info string loaded session from {}", path);
+                            }
+                            Err(error) => println!("info string {}", error)
+                        }
+                    }
+                    // Datagen's notion of quietness for the current position, and where qsearch settles
+                    ["quiet"] => {
+                        let quiet = is_quiet(&mut board, &info);
+                        let (_, resolution) = resolve_to_quiet(&board, &mut info);
+
+                        let mut line = vec![];
+                        let mut states = vec![];
+                        for &action in &resolution {
+                            line.push(board.display_uci_action(action));
+                            states.push(board.play(action));
+                        }
+                        while let Some(state) = states.pop() {
+                            board.restore(state);
+                        }
+
+                        println!("info string quiet {} resolves with {}", quiet, if line.is_empty() { "nothing".to_string() } else { line.join(" ") });
+                    }
+                    // The full position as the engine sees it, clocks included
+                    ["fen"] => {
+                        println!("{}", export_fen(&board, current_fen.as_deref(), &current_actions, info.root_halfmove));
+                    }
+                    // For chasing eval asymmetries: `mirror` swaps the colours and ranks, `flip` passes the move
+                    ["flip"] | ["mirror"] => {
+                        if args[0] == "flip" && in_check(&mut board) {
+                            println!("info string can't flip, the side to move is in check");
+                            continue;
+                        }
+
+                        let fen = export_fen(&board, current_fen.as_deref(), &current_actions, info.root_halfmove);
+                        let fen = if args[0] == "flip" { flip_fen(&fen) } else { mirror_fen(&board, &fen) };
+
+                        board = chess.load(&fen);
+                        current_moves = vec![];
+                        current_actions = play_moves(&mut board, &mut info, Some(&fen), &current_moves);
+                        println!("info string position {}", fen);
+                        current_fen = Some(fen);
+                    }
+                    // Classical eval weights as TOML, for trying tuner output without a rebuild
+                    ["export", "params", path] => {
+                        match export_params(path, &base_eval_params) {
+                            Ok(()) => println!("info string exported eval params to {}", path),
+                            Err(error) => println!("info string {}", error)
+                        }
+                    }
+                    ["import", "params", path] => {
+                        match import_params(path) {
+                            Ok(params) => {
+                                info.eval_params = apply_personality(&params, &personality);
+                                base_eval_params = params;
+                                println!("info string imported eval params from {}", path);
+                            }
+                            Err(error) => println!("info string {}", error)
+                        }
+                    }
+                    ["stats"] => {
+                        for line in stats_report(&info.stats) {
+                            println!("{}", line);
+                        }
+                    }
+                    ["spsa"] => {
+                        for input in spsa_inputs() {
+                            println!("{}", input);
+                        }
+                    }
+                    ["setoption", ..] => {
+                        let (name, value) = match parse_setoption(&args) {
+                            Some(option) => option,
+                            None => {
+                                println!("info string malformed setoption: {}", line);
+                                continue;
+                            }
+                        };
+
+                        match validate_option(&name, &value) {
+                            Ok(("ShowSAN", OptionValue::Check(value))) => {
+                                info.san_output = value;
+                            }
+                            Ok(("Kibitz", OptionValue::Check(value))) => {
+                                info.kibitz = value;
+                            }
+                            Ok(("TimemanReport", OptionValue::Check(value))) => {
+                                timeman_report = value;
+                            }
+                            Ok(("Contempt", OptionValue::Spin(value))) => {
+                                contempt = value as i32;
+                                info.contempt = contempt + profile.contempt + personality.contempt;
+                            }
+                            Ok(("MultiPV", OptionValue::Spin(value))) => {
+                                // A search that saw it arrive already switched over
+                                input::take_pending_multipv();
+                                info.multipv = value as usize;
+                            }
+                            Ok(("UCI_ShowWDL", OptionValue::Check(value))) => {
+                                info.show_wdl = value;
+                            }
+                            Ok(("RootNoise", OptionValue::Spin(value))) => {
+                                info.root_noise = value as i32;
+                            }
+                            Ok(("RootNoisePlies", OptionValue::Spin(value))) => {
+                                info.root_noise_plies = value as usize;
+                            }
+                            Ok(("RootNoiseSeed", OptionValue::Spin(value))) => {
+                                info.root_noise_seed = value as u64;
+                            }
+                            Ok(("StrictUCI", OptionValue::Check(value))) => {
+                                input::set_strict(value);
+                            }
+                            Ok((name, OptionValue::Check(value))) if find_ablation(name).is_some() => {
+                                let term = find_ablation(name).expect("Checked by the guard");
+                                info.ablated.retain(|&ablated| ablated != term);
+                                if value {
+                                    info.ablated.push(term);
+                                }
+                            }
+                            Ok(("MaxMoveTime", OptionValue::Spin(value))) => {
+                                max_move_time = value as u64;
+                            }
+                            Ok(("Hash", OptionValue::Spin(value))) => {
+                                info.tt = Arc::new(create_tt(tt_entries(value as usize)));
+                            }
+                            Ok(("PermanentBrain", OptionValue::Check(value))) => {
+                                permanent_brain = value;
+                            }
+                            Ok(("Threads", OptionValue::Spin(value))) => {
+                                info.threads = value as usize;
+                            }
+                            Ok(("TTReplace", OptionValue::String(name))) => {
+                                info.tt_replacement = find_replacement(&name).expect("Replacement values are listed");
+                            }
+                            Ok(("ProfileFile", OptionValue::String(path))) => {
+                                if path.is_empty() || path == "<empty>" {
+                                    profile_rules = vec![];
+                                } else {
+                                    match load_profiles(&path) {
+                                        Ok(rules) => profile_rules = rules,
+                                        Err(error) => println!("info string {}", error)
+                                    }
+                                }
+
+                                profile = select_profile(&profile_rules, &opponent);
+                                info.contempt = contempt + profile.contempt + personality.contempt;
+                            }
+                            Ok(("UCI_Opponent", OptionValue::String(value))) => {
+                                opponent = parse_opponent(&value);
+                                profile = select_profile(&profile_rules, &opponent);
+                                info.contempt = contempt + profile.contempt + personality.contempt;
+                                println!("info string opponent {} uses contempt {} time {}%", opponent.name, profile.contempt, profile.time_percent);
+                            }
+                            Ok(("Personality", OptionValue::String(name))) => {
+                                personality = *find_personality(&name).expect("Personality values are listed");
+                                info.eval_params = apply_personality(&base_eval_params, &personality);
+                                info.contempt = contempt + profile.contempt + personality.contempt;
+                            }
+                            Ok(("EvalFile", OptionValue::String(path))) => {
+                                if path.is_empty() || path == "<default>" {
+                                    network = default_network.clone();
+                                    info.nnue = network.clone().map(create_nnue_state);
+                                } else {
+                                    match load_network(&path) {
+                                        Ok(loaded) => {
+                                            println!("info string loaded network {} ({:016x})", path, loaded.hash);
+                                            network = Some(Arc::new(loaded));
+                                            info.nnue = network.clone().map(create_nnue_state);
+                                        }
+                                        Err(error) => {
+                                            println!("info string failed to load {}: {}", path, error);
+                                        }
+                                    }
+                                }
+                            }
+                            Ok((name, OptionValue::Spin(value))) => {
+                                set_param(&mut info.params, name, value as i32);
+                            }
+                            Ok((name, _)) => {
+                                println!("info string option {} has no handler", name);
+                            }
+                            Err(error) => {
+                                println!("info string {}", error);
+                            }
+                        }
+                    }
+                    _ => {
+                        if !input::is_strict() {
+                            println!("info string unknown command {}", line);
+                        }
+                    }
+                }
             }
         }
     }
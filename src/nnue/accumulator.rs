@@ -0,0 +1,91 @@
+use super::{simd::{add_weights, sub_weights}, Network, HIDDEN, INPUTS};
+
+// [team][piece] occupancy for the six chess piece types
+pub type PieceMasks = [[u64; 6]; 2];
+
+#[derive(Clone, Debug)]
+pub struct Accumulator {
+    // [perspective][hidden]
+    pub values: [Vec<i16>; 2],
+    pub buckets: [usize; 2],
+    pub masks: PieceMasks
+}
+
+// Finny table entry: the last accumulator computed for a given perspective
+// and king bucket, along with the pieces it was computed from
+#[derive(Clone, Debug)]
+pub struct FinnyEntry {
+    pub values: Vec<i16>,
+    pub masks: PieceMasks
+}
+
+pub fn feature_index(perspective: usize, bucket: usize, team: usize, piece: usize, sq: usize) -> usize {
+    // Both perspectives see their own pieces first, from their own side of the board
+    let (team, sq) = if perspective == 0 { (team, sq) } else { (team ^ 1, sq ^ 56) };
+
+    (bucket * INPUTS + team * 384 + piece * 64 + sq) * HIDDEN
+}
+
+fn squares(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+
+        let sq = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(sq)
+    })
+}
+
+// Brings `values` from the position described by `from` to the one described by `to`,
+// only touching the features that actually changed
+pub fn apply_diff(
+    network: &Network, 
+    values: &mut [i16], 
+    perspective: usize, 
+    bucket: usize, 
+    from: &PieceMasks, 
+    to: &PieceMasks
+) {
+    for team in 0..2 {
+        for piece in 0..6 {
+            let removed = from[team][piece] & !to[team][piece];
+            let added = to[team][piece] & !from[team][piece];
+
+            for sq in squares(removed) {
+                let index = feature_index(perspective, bucket, team, piece, sq);
+                sub_weights(values, &network.feature_weights[index..index + HIDDEN]);
+            }
+
+            for sq in squares(added) {
+                let index = feature_index(perspective, bucket, team, piece, sq);
+                add_weights(values, &network.feature_weights[index..index + HIDDEN]);
+            }
+        }
+    }
+}
+
+pub fn create_finny_table(network: &Network) -> Vec<Vec<FinnyEntry>> {
+    let entry = FinnyEntry {
+        values: network.feature_bias.clone(),
+        masks: [[0; 6]; 2]
+    };
+
+    vec![ vec![ entry; super::KING_BUCKETS ]; 2 ]
+}
+
+// A king bucket change invalidates the parent accumulator, but the finny entry
+// for the new bucket is usually only a handful of pieces away from the position
+pub fn refresh_from_finny(
+    network: &Network, 
+    entry: &mut FinnyEntry, 
+    perspective: usize, 
+    bucket: usize, 
+    masks: &PieceMasks
+) -> Vec<i16> {
+    apply_diff(network, &mut entry.values, perspective, bucket, &entry.masks, masks);
+    entry.masks = *masks;
+
+    entry.values.clone()
+}
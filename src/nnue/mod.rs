@@ -0,0 +1,250 @@
+use std::{fs, sync::Arc};
+
+use accumulator::{apply_diff, create_finny_table, refresh_from_finny, Accumulator, FinnyEntry, PieceMasks};
+use chessing::{bitboard::BitInt, game::{Board, Team}};
+use simd::dot;
+
+mod accumulator;
+mod simd;
+
+pub const INPUTS: usize = 768;
+pub const KING_BUCKETS: usize = 4;
+pub const HIDDEN: usize = 256;
+pub const L1: usize = 16;
+pub const L2: usize = 32;
+
+// Activations are quantized to [0, QA] and weights are scaled by QB,
+// so every layer's output is shifted back down by log2(QB)
+pub const QA: i32 = 127;
+pub const QB: i32 = 64;
+pub const WEIGHT_SHIFT: i32 = 6;
+pub const SCALE: i32 = 400;
+
+const KING: usize = 5;
+
+// Indexed by the king's square as seen from its own side (a1 = 0)
+pub const KING_BUCKET_LAYOUT: [usize; 64] = [
+    0, 0, 0, 0, 1, 1, 1, 1,
+    2, 2, 2, 2, 2, 2, 2, 2,
+    2, 2, 2, 2, 2, 2, 2, 2,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+];
+
+pub struct Network {
+    pub hash: u64,
+    // [bucket][input][hidden]
+    pub feature_weights: Vec<i16>,
+    pub feature_bias: Vec<i16>,
+    // [L1][perspective][hidden], side to move first
+    pub l1_weights: Vec<i8>,
+    pub l1_bias: Vec<i32>,
+    // [L2][L1]
+    pub l2_weights: Vec<i8>,
+    pub l2_bias: Vec<i32>,
+    pub output_weights: Vec<i8>,
+    pub output_bias: i32
+}
+
+fn take<'a>(bytes: &mut &'a [u8], count: usize) -> Result<&'a [u8], String> {
+    if bytes.len() < count {
+        return Err("network file is truncated".to_string());
+    }
+
+    let (taken, rest) = bytes.split_at(count);
+    *bytes = rest;
+
+    Ok(taken)
+}
+
+fn read_i8s(bytes: &mut &[u8], count: usize) -> Result<Vec<i8>, String> {
+    Ok(take(bytes, count)?.iter().map(|&byte| byte as i8).collect())
+}
+
+fn read_i16s(bytes: &mut &[u8], count: usize) -> Result<Vec<i16>, String> {
+    Ok(take(bytes, count * 2)?
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([ pair[0], pair[1] ]))
+        .collect())
+}
+
+fn read_i32s(bytes: &mut &[u8], count: usize) -> Result<Vec<i32>, String> {
+    Ok(take(bytes, count * 4)?
+        .chunks_exact(4)
+        .map(|quad| i32::from_le_bytes([ quad[0], quad[1], quad[2], quad[3] ]))
+        .collect())
+}
+
+pub const NETWORK_MAGIC: &[u8; 4] = b"ARTN";
+
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+// A network file is `ARTN`, the FNV-1a hash of the payload, and then the payload:
+// every field stored little-endian in declaration order
+pub fn parse_network(mut bytes: &[u8]) -> Result<Network, String> {
+    let bytes = &mut bytes;
+
+    if take(bytes, 4)? != NETWORK_MAGIC {
+        return Err("not an Artifact network file".to_string());
+    }
+
+    let hash = u64::from_le_bytes(take(bytes, 8)?.try_into().expect("Exactly 8 bytes"));
+    if fnv1a(bytes) != hash {
+        return Err(format!("hash mismatch, expected {:016x} but the file is {:016x}", hash, fnv1a(bytes)));
+    }
+
+    Ok(Network {
+        hash,
+        feature_weights: read_i16s(bytes, KING_BUCKETS * INPUTS * HIDDEN)?,
+        feature_bias: read_i16s(bytes, HIDDEN)?,
+        l1_weights: read_i8s(bytes, L1 * 2 * HIDDEN)?,
+        l1_bias: read_i32s(bytes, L1)?,
+        l2_weights: read_i8s(bytes, L2 * L1)?,
+        l2_bias: read_i32s(bytes, L2)?,
+        output_weights: read_i8s(bytes, L2)?,
+        output_bias: read_i32s(bytes, 1)?[0]
+    })
+}
+
+pub fn load_network(path: &str) -> Result<Network, String> {
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+    parse_network(&bytes)
+}
+
+#[cfg(feature = "embedded-net")]
+static EMBEDDED_NETWORK: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/networks/default.nnue"));
+
+// The network compiled into the binary, if built with `--features embedded-net`
+#[cfg(feature = "embedded-net")]
+pub fn embedded_network() -> Option<Result<Network, String>> {
+    Some(parse_network(EMBEDDED_NETWORK))
+}
+
+#[cfg(not(feature = "embedded-net"))]
+pub fn embedded_network() -> Option<Result<Network, String>> {
+    None
+}
+
+#[derive(Clone)]
+pub struct NnueState {
+    pub network: Arc<Network>,
+    // Accumulators by ply, so unmaking a move is just returning to the parent's entry
+    pub stack: Vec<Option<Accumulator>>,
+    // [perspective][bucket]
+    pub finny: Vec<Vec<FinnyEntry>>
+}
+
+pub fn create_nnue_state(network: Arc<Network>) -> NnueState {
+    NnueState {
+        finny: create_finny_table(&network),
+        stack: vec![ None; 128 ],
+        network
+    }
+}
+
+fn team_index(team: Team) -> usize {
+    match team {
+        Team::White => 0,
+        Team::Black => 1
+    }
+}
+
+pub fn piece_masks<T: BitInt, const N: usize>(board: &Board<T, N>) -> PieceMasks {
+    let mut masks = [[0; 6]; 2];
+
+    for (piece, pieces) in board.state.pieces.iter().enumerate().take(6) {
+        for sq in pieces.and(board.state.white).iter() {
+            masks[0][piece] |= 1u64 << sq as usize;
+        }
+        for sq in pieces.and(board.state.black).iter() {
+            masks[1][piece] |= 1u64 << sq as usize;
+        }
+    }
+
+    masks
+}
+
+pub fn king_bucket(perspective: usize, king: usize) -> usize {
+    let king = king.min(63);
+    let relative = if perspective == 0 { king } else { king ^ 56 };
+
+    KING_BUCKET_LAYOUT[relative]
+}
+
+fn activate(sum: i32) -> u8 {
+    (sum >> WEIGHT_SHIFT).clamp(0, QA) as u8
+}
+
+fn output(network: &Network, us: &[i16], them: &[i16]) -> i32 {
+    let mut features = [ 0u8; 2 * HIDDEN ];
+    for (feature, &value) in features.iter_mut().zip(us.iter().chain(them)) {
+        *feature = (value as i32).clamp(0, QA) as u8;
+    }
+
+    let mut l1 = [ 0u8; L1 ];
+    for (i, neuron) in l1.iter_mut().enumerate() {
+        let weights = &network.l1_weights[i * 2 * HIDDEN..(i + 1) * 2 * HIDDEN];
+        *neuron = activate(dot(&features, weights) + network.l1_bias[i]);
+    }
+
+    let mut l2 = [ 0u8; L2 ];
+    for (i, neuron) in l2.iter_mut().enumerate() {
+        let weights = &network.l2_weights[i * L1..(i + 1) * L1];
+        *neuron = activate(dot(&l1, weights) + network.l2_bias[i]);
+    }
+
+    let sum = dot(&l2, &network.output_weights) + network.output_bias;
+    sum * SCALE / (QA * QB)
+}
+
+pub fn evaluate_nnue<T: BitInt, const N: usize>(
+    board: &mut Board<T, N>,
+    state: &mut NnueState,
+    ply: usize
+) -> i32 {
+    let NnueState { network, stack, finny } = state;
+    let network: &Network = network;
+
+    if stack.len() <= ply {
+        stack.resize(ply + 1, None);
+    }
+
+    let masks = piece_masks(board);
+    let parent = stack[ply.saturating_sub(1)].as_ref();
+
+    let mut values = [ vec![], vec![] ];
+    let mut buckets = [ 0; 2 ];
+
+    for perspective in 0..2 {
+        let king = masks[perspective][KING].trailing_zeros() as usize;
+        let bucket = king_bucket(perspective, king);
+        buckets[perspective] = bucket;
+
+        values[perspective] = match parent {
+            Some(parent) if parent.buckets[perspective] == bucket => {
+                let mut values = parent.values[perspective].clone();
+                apply_diff(network, &mut values, perspective, bucket, &parent.masks, &masks);
+                values
+            }
+            _ => refresh_from_finny(network, &mut finny[perspective][bucket], perspective, bucket, &masks)
+        };
+    }
+
+    let us = team_index(board.state.moving_team);
+    let score = output(network, &values[us], &values[us ^ 1]);
+
+    stack[ply] = Some(Accumulator { values, buckets, masks });
+
+    score
+}
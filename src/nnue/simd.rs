@@ -0,0 +1,211 @@
+use std::sync::OnceLock;
+
+#[allow(dead_code)] // Only the current architecture's kernels are ever constructed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kernel {
+    Scalar,
+    Avx2,
+    Neon
+}
+
+static KERNEL: OnceLock<Kernel> = OnceLock::new();
+
+fn detect_kernel() -> Kernel {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return Kernel::Avx2;
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return Kernel::Neon;
+        }
+    }
+
+    Kernel::Scalar
+}
+
+// Detected once, the first time a network is used
+pub fn kernel() -> Kernel {
+    *KERNEL.get_or_init(detect_kernel)
+}
+
+pub fn add_weights(values: &mut [i16], weights: &[i16]) {
+    // Panics on short weights instead of letting the kernels read past them
+    let weights = &weights[..values.len()];
+
+    match kernel() {
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { avx2::add_weights(values, weights) },
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { neon::add_weights(values, weights) },
+        _ => scalar::add_weights(values, weights)
+    }
+}
+
+pub fn sub_weights(values: &mut [i16], weights: &[i16]) {
+    let weights = &weights[..values.len()];
+
+    match kernel() {
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { avx2::sub_weights(values, weights) },
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { neon::sub_weights(values, weights) },
+        _ => scalar::sub_weights(values, weights)
+    }
+}
+
+// Activations are at most 127 so the pairwise i16 sums in `maddubs` can't saturate
+pub fn dot(activations: &[u8], weights: &[i8]) -> i32 {
+    let weights = &weights[..activations.len()];
+
+    match kernel() {
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { avx2::dot(activations, weights) },
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { neon::dot(activations, weights) },
+        _ => scalar::dot(activations, weights)
+    }
+}
+
+mod scalar {
+    pub fn add_weights(values: &mut [i16], weights: &[i16]) {
+        for (value, &weight) in values.iter_mut().zip(weights) {
+            *value += weight;
+        }
+    }
+
+    pub fn sub_weights(values: &mut [i16], weights: &[i16]) {
+        for (value, &weight) in values.iter_mut().zip(weights) {
+            *value -= weight;
+        }
+    }
+
+    pub fn dot(activations: &[u8], weights: &[i8]) -> i32 {
+        activations.iter()
+            .zip(weights)
+            .map(|(&activation, &weight)| activation as i32 * weight as i32)
+            .sum()
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    const I16_LANES: usize = 16;
+    const I8_LANES: usize = 32;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add_weights(values: &mut [i16], weights: &[i16]) {
+        let chunks = values.len() / I16_LANES;
+
+        for i in 0..chunks {
+            let value = _mm256_loadu_si256(values.as_ptr().add(i * I16_LANES) as *const __m256i);
+            let weight = _mm256_loadu_si256(weights.as_ptr().add(i * I16_LANES) as *const __m256i);
+            _mm256_storeu_si256(values.as_mut_ptr().add(i * I16_LANES) as *mut __m256i, _mm256_add_epi16(value, weight));
+        }
+
+        let done = chunks * I16_LANES;
+        super::scalar::add_weights(&mut values[done..], &weights[done..]);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub_weights(values: &mut [i16], weights: &[i16]) {
+        let chunks = values.len() / I16_LANES;
+
+        for i in 0..chunks {
+            let value = _mm256_loadu_si256(values.as_ptr().add(i * I16_LANES) as *const __m256i);
+            let weight = _mm256_loadu_si256(weights.as_ptr().add(i * I16_LANES) as *const __m256i);
+            _mm256_storeu_si256(values.as_mut_ptr().add(i * I16_LANES) as *mut __m256i, _mm256_sub_epi16(value, weight));
+        }
+
+        let done = chunks * I16_LANES;
+        super::scalar::sub_weights(&mut values[done..], &weights[done..]);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn dot(activations: &[u8], weights: &[i8]) -> i32 {
+        let chunks = activations.len() / I8_LANES;
+        let ones = _mm256_set1_epi16(1);
+        let mut sum = _mm256_setzero_si256();
+
+        for i in 0..chunks {
+            let activation = _mm256_loadu_si256(activations.as_ptr().add(i * I8_LANES) as *const __m256i);
+            let weight = _mm256_loadu_si256(weights.as_ptr().add(i * I8_LANES) as *const __m256i);
+
+            let pairs = _mm256_maddubs_epi16(activation, weight);
+            sum = _mm256_add_epi32(sum, _mm256_madd_epi16(pairs, ones));
+        }
+
+        let sum = _mm_add_epi32(_mm256_castsi256_si128(sum), _mm256_extracti128_si256(sum, 1));
+        let sum = _mm_add_epi32(sum, _mm_unpackhi_epi64(sum, sum));
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 1));
+
+        let done = chunks * I8_LANES;
+        _mm_cvtsi128_si32(sum) + super::scalar::dot(&activations[done..], &weights[done..])
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    const I16_LANES: usize = 8;
+    const I8_LANES: usize = 16;
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn add_weights(values: &mut [i16], weights: &[i16]) {
+        let chunks = values.len() / I16_LANES;
+
+        for i in 0..chunks {
+            let value = vld1q_s16(values.as_ptr().add(i * I16_LANES));
+            let weight = vld1q_s16(weights.as_ptr().add(i * I16_LANES));
+            vst1q_s16(values.as_mut_ptr().add(i * I16_LANES), vaddq_s16(value, weight));
+        }
+
+        let done = chunks * I16_LANES;
+        super::scalar::add_weights(&mut values[done..], &weights[done..]);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn sub_weights(values: &mut [i16], weights: &[i16]) {
+        let chunks = values.len() / I16_LANES;
+
+        for i in 0..chunks {
+            let value = vld1q_s16(values.as_ptr().add(i * I16_LANES));
+            let weight = vld1q_s16(weights.as_ptr().add(i * I16_LANES));
+            vst1q_s16(values.as_mut_ptr().add(i * I16_LANES), vsubq_s16(value, weight));
+        }
+
+        let done = chunks * I16_LANES;
+        super::scalar::sub_weights(&mut values[done..], &weights[done..]);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn dot(activations: &[u8], weights: &[i8]) -> i32 {
+        let chunks = activations.len() / I8_LANES;
+        let mut sum = vdupq_n_s32(0);
+
+        for i in 0..chunks {
+            let activation = vld1q_u8(activations.as_ptr().add(i * I8_LANES));
+            let weight = vld1q_s8(weights.as_ptr().add(i * I8_LANES));
+
+            let activation_low = vreinterpretq_s16_u16(vmovl_u8(vget_low_u8(activation)));
+            let activation_high = vreinterpretq_s16_u16(vmovl_high_u8(activation));
+            let weight_low = vmovl_s8(vget_low_s8(weight));
+            let weight_high = vmovl_high_s8(weight);
+
+            sum = vmlal_s16(sum, vget_low_s16(activation_low), vget_low_s16(weight_low));
+            sum = vmlal_high_s16(sum, activation_low, weight_low);
+            sum = vmlal_s16(sum, vget_low_s16(activation_high), vget_low_s16(weight_high));
+            sum = vmlal_high_s16(sum, activation_high, weight_high);
+        }
+
+        let done = chunks * I8_LANES;
+        vaddvq_s32(sum) + super::scalar::dot(&activations[done..], &weights[done..])
+    }
+}
@@ -0,0 +1,141 @@
+use crate::search::params::TUNABLES;
+
+#[derive(Clone, Debug)]
+pub enum OptionKind {
+    Check { default: bool },
+    Spin { default: i64, min: i64, max: i64 },
+    String { default: &'static str },
+    Combo { default: &'static str, values: &'static [&'static str] },
+    Button
+}
+
+#[derive(Clone, Debug)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionKind
+}
+
+// A `setoption` value after checking it against the option's type
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionValue {
+    Check(bool),
+    Spin(i64),
+    String(String),
+    Button
+}
+
+// Grouped for GUIs that list options in the order they're sent: playing, analysis, time, then development
+pub const ENGINE_OPTIONS: [UciOption; 21] = [
+    // Megabytes, reallocating clears the table
+    UciOption { name: "Hash", kind: OptionKind::Spin { default: 16, min: 1, max: 65536 } },
+    // Lazy SMP, the extra threads only fill the shared TT
+    UciOption { name: "Threads", kind: OptionKind::Spin { default: 1, min: 1, max: 256 } },
+    UciOption { name: "EvalFile", kind: OptionKind::String { default: "<default>" } },
+    // Names from `personality::PERSONALITIES`
+    UciOption {
+        name: "Personality",
+        kind: OptionKind::Combo { default: "default", values: &[ "default", "aggressive", "solid", "gambit" ] }
+    },
+    // Thinks on the opponent's time after every move without `go ponder`, for bots
+    UciOption { name: "PermanentBrain", kind: OptionKind::Check { default: false } },
+    // Centipawns, on top of the opponent profile and personality
+    UciOption { name: "Contempt", kind: OptionKind::Spin { default: 0, min: -200, max: 200 } },
+    // See `profiles`, picked by UCI_Opponent
+    UciOption { name: "ProfileFile", kind: OptionKind::String { default: "<empty>" } },
+    UciOption { name: "UCI_Opponent", kind: OptionKind::String { default: "" } },
+    // Centipawns of seeded noise on root scores early in the game, 0 is off
+    UciOption { name: "RootNoise", kind: OptionKind::Spin { default: 0, min: 0, max: 100 } },
+    UciOption { name: "RootNoisePlies", kind: OptionKind::Spin { default: 8, min: 0, max: 200 } },
+    UciOption { name: "RootNoiseSeed", kind: OptionKind::Spin { default: 0, min: 0, max: i32::MAX as i64 } },
+    // Can change mid-analysis, which restarts the search
+    UciOption { name: "MultiPV", kind: OptionKind::Spin { default: 1, min: 1, max: 256 } },
+    UciOption { name: "UCI_ShowWDL", kind: OptionKind::Check { default: false } },
+    UciOption { name: "ShowSAN", kind: OptionKind::Check { default: false } },
+    UciOption { name: "Kibitz", kind: OptionKind::Check { default: false } },
+    // Milliseconds, 0 is off
+    UciOption { name: "MaxMoveTime", kind: OptionKind::Spin { default: 0, min: 0, max: 3_600_000 } },
+    UciOption { name: "TimemanReport", kind: OptionKind::Check { default: false } },
+    // Names from `search::tt::REPLACEMENTS`, compare them with `bench tt`
+    UciOption {
+        name: "TTReplace",
+        kind: OptionKind::Combo { default: "always", values: &[ "always", "depth", "aged" ] }
+    },
+    // See `input`
+    UciOption { name: "StrictUCI", kind: OptionKind::Check { default: false } },
+    // Classical eval terms to leave out, see `eval::EVAL_TERMS`
+    UciOption { name: "AblatePSQT", kind: OptionKind::Check { default: false } },
+    UciOption { name: "AblateMobility", kind: OptionKind::Check { default: false } }
+];
+
+// Everything advertised on `uci`, search tunables last
+pub fn uci_options() -> Vec<UciOption> {
+    let tunables = TUNABLES.iter().map(|tunable| UciOption {
+        name: tunable.name,
+        kind: OptionKind::Spin { default: tunable.default as i64, min: tunable.min as i64, max: tunable.max as i64 }
+    });
+
+    ENGINE_OPTIONS.iter().cloned().chain(tunables).collect()
+}
+
+pub fn display_option(option: &UciOption) -> String {
+    match &option.kind {
+        OptionKind::Check { default } => format!("option name {} type check default {}", option.name, default),
+        OptionKind::Spin { default, min, max } => format!(
+            "option name {} type spin default {} min {} max {}",
+            option.name, default, min, max
+        ),
+        OptionKind::String { default } => format!("option name {} type string default {}", option.name, default),
+        OptionKind::Combo { default, values } => format!(
+            "option name {} type combo default {} {}",
+            option.name, default, values.iter().map(|value| format!("var {}", value)).collect::<Vec<_>>().join(" ")
+        ),
+        OptionKind::Button => format!("option name {} type button", option.name)
+    }
+}
+
+// `setoption name <name...> [value <value...>]`, both sides may contain spaces
+pub fn parse_setoption(args: &[&str]) -> Option<(String, String)> {
+    let args = match args {
+        ["setoption", "name", rest @ ..] => rest,
+        _ => return None
+    };
+
+    let split = args.iter().position(|&arg| arg == "value").unwrap_or(args.len());
+    let name = args[..split].join(" ");
+    let value = args.get(split + 1..).map(|value| value.join(" ")).unwrap_or_default();
+
+    if name.is_empty() {
+        None
+    } else {
+        Some((name, value))
+    }
+}
+
+// Option names are case insensitive in UCI; values of the wrong type or out of range are
+// refused with a message saying why, rather than quietly changed
+pub fn validate_option(name: &str, value: &str) -> Result<(&'static str, OptionValue), String> {
+    let option = uci_options().into_iter()
+        .find(|option| option.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown option {}", name))?;
+
+    let value = match option.kind {
+        OptionKind::Check { .. } => match value {
+            "true" => OptionValue::Check(true),
+            "false" => OptionValue::Check(false),
+            _ => return Err(format!("{} expects true or false, got {}", option.name, value))
+        },
+        OptionKind::Spin { min, max, .. } => match value.parse::<i64>() {
+            Ok(value) if (min..=max).contains(&value) => OptionValue::Spin(value),
+            Ok(value) => return Err(format!("{} must be between {} and {}, got {}", option.name, min, max, value)),
+            Err(_) => return Err(format!("{} expects an integer, got {}", option.name, value))
+        },
+        OptionKind::String { .. } => OptionValue::String(value.to_string()),
+        OptionKind::Combo { values, .. } => match values.iter().find(|var| var.eq_ignore_ascii_case(value)) {
+            Some(var) => OptionValue::String(var.to_string()),
+            None => return Err(format!("{} expects one of {}, got {}", option.name, values.join(", "), value))
+        },
+        OptionKind::Button => OptionValue::Button
+    };
+
+    Ok((option.name, value))
+}
@@ -0,0 +1,94 @@
+use chessing::{bitboard::BitInt, chess::Chess, game::{Board, GameTemplate}};
+
+use crate::{bench::nps, search::legal_actions, util::current_time_millis, variants::{GameKind, Variant}};
+
+pub fn perft<T: BitInt, const N: usize>(board: &mut Board<T, N>, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let actions = legal_actions(board);
+    if depth == 1 {
+        return actions.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for action in actions {
+        let state = board.play(action);
+        nodes += perft(board, depth - 1);
+        board.restore(state);
+    }
+
+    nodes
+}
+
+// Perft split by root move, each named in UCI notation, for narrowing a mismatch down to one line
+pub fn perft_divide<T: BitInt, const N: usize>(board: &mut Board<T, N>, depth: u32) -> Vec<(String, u64)> {
+    let mut divided = vec![];
+
+    for action in legal_actions(board) {
+        let name = board.display_uci_action(action);
+        let state = board.play(action);
+        divided.push((name, perft(board, depth.saturating_sub(1))));
+        board.restore(state);
+    }
+
+    divided
+}
+
+pub const MOVEGEN_SECONDS: f64 = 5.;
+// Roughly how much longer a run one ply deeper takes
+const MOVEGEN_BRANCHING: u128 = 40;
+
+// Legal move generation throughput in nodes per second, from perft runs filling `seconds`.
+// The depth grows while a run one ply deeper should still fit in the time left, otherwise the
+// last depth is repeated
+pub fn movegen_bench<T: BitInt, const N: usize>(board: &mut Board<T, N>, seconds: f64) -> u64 {
+    let start = current_time_millis();
+    let deadline = start + (seconds.max(0.) * 1000.) as u128;
+    let mut nodes = 0;
+    let mut depth = 1;
+
+    loop {
+        let run_start = current_time_millis();
+        nodes += perft(board, depth);
+
+        let now = current_time_millis();
+        if now >= deadline {
+            return nps(nodes, now - start);
+        }
+
+        if (now - run_start) * MOVEGEN_BRANCHING < deadline - now {
+            depth += 1;
+        }
+    }
+}
+
+// Runs every reference position for the variant, reporting each one; true if all matched
+pub fn validate(variant: &Variant) -> bool {
+    let chess = match variant.game {
+        GameKind::Chess => Chess::create::<u64, 6>()
+    };
+    let mut mismatches = 0;
+
+    for case in variant.perft {
+        let mut board = chess.load(case.fen);
+
+        let start = current_time_millis();
+        let nodes = perft(&mut board, case.depth);
+        let time = current_time_millis() - start;
+
+        let status = if nodes == case.nodes { "ok" } else { "MISMATCH" };
+        println!(
+            "{:<8} depth {} {:>12} nodes (expected {:>12}) {:>10} nps  {}",
+            status, case.depth, nodes, case.nodes, nps(nodes, time), case.fen
+        );
+
+        if nodes != case.nodes {
+            mismatches += 1;
+        }
+    }
+
+    println!("{}: {}/{} positions match", variant.name, variant.perft.len() - mismatches, variant.perft.len());
+    mismatches == 0
+}
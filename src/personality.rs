@@ -0,0 +1,37 @@
+use crate::eval::params::EvalParams;
+
+// A playing style as a tilt on the classical eval, for casual users who'd rather pick a
+// character than tune weights; NNUE scores only feel the contempt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Personality {
+    pub name: &'static str,
+    // Scales piece values, below 100 gives up material more readily
+    pub material_percent: i32,
+    // Scales the mobility bonus, the eval's stand-in for initiative
+    pub mobility_percent: i32,
+    // Added to the opponent profile's contempt
+    pub contempt: i32
+}
+
+pub const PERSONALITIES: [Personality; 4] = [
+    Personality { name: "default", material_percent: 100, mobility_percent: 100, contempt: 0 },
+    Personality { name: "aggressive", material_percent: 100, mobility_percent: 200, contempt: 25 },
+    Personality { name: "solid", material_percent: 105, mobility_percent: 75, contempt: -10 },
+    Personality { name: "gambit", material_percent: 85, mobility_percent: 300, contempt: 40 }
+];
+
+pub fn find_personality(name: &str) -> Option<&'static Personality> {
+    PERSONALITIES.iter().find(|personality| personality.name.eq_ignore_ascii_case(name))
+}
+
+// Applied to a copy of the base weights so switching personalities doesn't compound
+pub fn apply_personality(base: &EvalParams, personality: &Personality) -> EvalParams {
+    let mut params = base.clone();
+
+    for value in &mut params.material {
+        *value = *value * personality.material_percent / 100;
+    }
+    params.mobility = params.mobility * personality.mobility_percent / 100;
+
+    params
+}
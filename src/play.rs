@@ -0,0 +1,223 @@
+use std::io::{self, Write};
+
+use chessing::{bitboard::BitInt, chess::Chess, game::{action::Action, Board, GameState, GameTemplate, Team}};
+
+use crate::{fen::square_chars, san::{display_san, parse_san}, search::{create_search_info, legal_actions, search_to_depth, Score, SearchInfo}, variants::{configure_search, find_variant, GameKind, Variant, VARIANTS}};
+
+pub struct PlayOptions {
+    pub variant: &'static Variant,
+    pub human: Team,
+    pub depth: i32,
+    pub movetime: u64
+}
+
+// play [white|black] [variant <name>] [depth <plies>] [movetime <ms>]
+pub fn parse_play_options(args: &[&str]) -> PlayOptions {
+    let mut options = PlayOptions {
+        variant: &VARIANTS[0],
+        human: Team::White,
+        depth: 99,
+        movetime: 1000
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1).copied().unwrap_or("");
+
+        match args[i] {
+            "white" => options.human = Team::White,
+            "black" => options.human = Team::Black,
+            "variant" => {
+                match find_variant(value) {
+                    Some(variant) => options.variant = variant,
+                    None => println!("Unknown variant `{}`, playing {}", value, options.variant.name)
+                }
+                i += 1;
+            }
+            "depth" => {
+                options.depth = value.parse().unwrap_or(options.depth).clamp(1, 99);
+                i += 1;
+            }
+            "movetime" => {
+                options.movetime = value.parse().unwrap_or(options.movetime);
+                i += 1;
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    options
+}
+
+pub fn team_name(team: Team) -> &'static str {
+    match team {
+        Team::White => "White",
+        Team::Black => "Black"
+    }
+}
+
+pub fn print_board<T: BitInt, const N: usize>(board: &mut Board<T, N>) {
+    let rows = board.game.bounds.rows as usize;
+    let cols = board.game.bounds.cols as usize;
+    let squares = square_chars(board);
+
+    println!();
+    for row in (0..rows).rev() {
+        print!("{:>2} ", row + 1);
+        for col in 0..cols {
+            print!(" {}", squares[row * cols + col].unwrap_or('.'));
+        }
+        println!();
+    }
+
+    print!("   ");
+    for col in 0..cols {
+        print!(" {}", (b'a' + col as u8) as char);
+    }
+    println!();
+    println!();
+    println!("{} to move", team_name(board.state.moving_team));
+}
+
+fn game_result<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo) -> Option<String> {
+    let actions = legal_actions(board);
+
+    match board.game_state(&actions) {
+        GameState::Win(team) => {
+            return Some(format!("{} wins", team_name(team)));
+        }
+        GameState::Draw => {
+            return Some("Draw".to_string());
+        }
+        GameState::Ongoing => {}
+    }
+
+    let hash = board.game.rules.hash(board, &info.zobrist);
+    if info.hashes.iter().filter(|&&seen| seen == hash).count() >= 3 {
+        return Some("Draw by threefold repetition".to_string());
+    }
+
+    None
+}
+
+// Accepts both coordinate notation and SAN
+fn find_action<T: BitInt, const N: usize>(board: &mut Board<T, N>, input: &str) -> Option<Action> {
+    legal_actions(board)
+        .into_iter()
+        .find(|&action| board.display_uci_action(action) == input)
+        .or_else(|| parse_san(board, input))
+}
+
+fn play_game<T: BitInt, const N: usize, I: Iterator<Item = io::Result<String>>>(
+    lines: &mut I,
+    board: &mut Board<T, N>,
+    options: &PlayOptions
+) {
+    let mut info = create_search_info(board);
+    configure_search(&mut info, options.variant);
+    info.hashes = vec![ board.game.rules.hash(board, &info.zobrist) ];
+
+    let mut states = vec![];
+
+    println!("Playing {} as {}. Enter moves (UCI or SAN), `undo`, `board` or `quit`.", options.variant.name, team_name(options.human));
+    print_board(board);
+
+    loop {
+        let result = game_result(board, &info);
+
+        if let Some(result) = &result {
+            println!("{}", result);
+        } else if board.state.moving_team != options.human {
+            match search_to_depth(&mut info, board, options.depth, options.movetime / 2, options.movetime) {
+                Some(found) => {
+                    let action = found.best;
+                    let score = match found.score {
+                        Score::Cp(score) => format!("{} cp", score),
+                        Score::Mate(moves) => format!("mate {}", moves)
+                    };
+
+                    println!("Artifact plays {} ({})", display_san(board, action), score);
+
+                    states.push(board.play(action));
+                    info.hashes.push(board.game.rules.hash(board, &info.zobrist));
+                    print_board(board);
+                }
+                None => {
+                    println!("Artifact has no move to play");
+                    return;
+                }
+            }
+
+            continue;
+        }
+
+        print!("> ");
+        io::stdout().flush().expect("Stdout is writable");
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return
+        };
+
+        match line.trim() {
+            "" => {}
+            "quit" | "exit" => {
+                return;
+            }
+            "board" | "d" => {
+                print_board(board);
+            }
+            "undo" => {
+                // Take back the engine's reply along with the player's own move
+                let mut undone = 0;
+                while let Some(state) = states.pop() {
+                    board.restore(state);
+                    info.hashes.pop();
+                    undone += 1;
+
+                    if board.state.moving_team == options.human {
+                        break;
+                    }
+                }
+
+                if undone == 0 {
+                    println!("Nothing to take back");
+                } else {
+                    print_board(board);
+                }
+            }
+            input => {
+                if result.is_some() {
+                    println!("The game is over, `undo` or `quit`");
+                    continue;
+                }
+
+                match find_action(board, input) {
+                    Some(action) => {
+                        states.push(board.play(action));
+                        info.hashes.push(board.game.rules.hash(board, &info.zobrist));
+                        print_board(board);
+                    }
+                    None => {
+                        println!("Illegal or unrecognized move `{}`", input);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn play<I: Iterator<Item = io::Result<String>>>(lines: &mut I, args: &[&str]) {
+    let options = parse_play_options(args);
+
+    match options.variant.game {
+        GameKind::Chess => {
+            let chess = Chess::create::<u64, 6>();
+            let mut board = chess.default();
+
+            play_game(lines, &mut board, &options);
+        }
+    }
+}
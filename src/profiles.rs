@@ -0,0 +1,107 @@
+use std::fs;
+
+// How to play against a particular opponent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Profile {
+    // Centipawns a draw is worth less than zero to us, positive plays for a win
+    pub contempt: i32,
+    // Soft time as a percentage of what the clock math gives
+    pub time_percent: u64
+}
+
+pub const DEFAULT_PROFILE: Profile = Profile { contempt: 0, time_percent: 100 };
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Matcher {
+    Name(String),
+    // Opponents rated below this
+    Below(u32),
+    Default
+}
+
+#[derive(Clone, Debug)]
+pub struct ProfileRule {
+    pub matcher: Matcher,
+    pub profile: Profile
+}
+
+// `UCI_Opponent` is `<title> <elo> <computer|human> <name>`, with `none` for unknown fields
+#[derive(Clone, Debug, Default)]
+pub struct Opponent {
+    pub title: Option<String>,
+    pub elo: Option<u32>,
+    pub computer: bool,
+    pub name: String
+}
+
+pub fn parse_opponent(value: &str) -> Opponent {
+    let mut parts = value.split_whitespace();
+    let known = |part: Option<&str>| part.filter(|&part| part != "none").map(|part| part.to_string());
+
+    let title = known(parts.next());
+    let elo = known(parts.next()).and_then(|elo| elo.parse().ok());
+    let computer = parts.next() == Some("computer");
+    let name = parts.collect::<Vec<_>>().join(" ");
+
+    Opponent { title, elo, computer, name }
+}
+
+// One rule per line: `<name|below:ELO|default> contempt=CP time=PERCENT`, `#` starts a comment
+pub fn load_profiles(path: &str) -> Result<Vec<ProfileRule>, String> {
+    let contents = fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let mut rules = vec![];
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let bad_line = || format!("{}:{}: malformed profile", path, number + 1);
+        let mut fields: Vec<&str> = line.split_whitespace().collect();
+
+        // Names can contain spaces, so settings are taken off the end
+        let mut profile = DEFAULT_PROFILE;
+        while let Some(&field) = fields.last() {
+            match field.split_once('=') {
+                Some(("contempt", value)) => profile.contempt = value.parse().map_err(|_| bad_line())?,
+                Some(("time", value)) => profile.time_percent = value.parse().map_err(|_| bad_line())?,
+                Some(_) => return Err(bad_line()),
+                None => break
+            }
+            fields.pop();
+        }
+
+        let key = fields.join(" ");
+        let matcher = match key.strip_prefix("below:") {
+            Some(elo) => Matcher::Below(elo.parse().map_err(|_| bad_line())?),
+            None if key == "default" => Matcher::Default,
+            None if key.is_empty() => return Err(bad_line()),
+            None => Matcher::Name(key)
+        };
+
+        rules.push(ProfileRule { matcher, profile });
+    }
+
+    Ok(rules)
+}
+
+// An exact name beats the tightest rating rule, which beats the default
+pub fn select_profile(rules: &[ProfileRule], opponent: &Opponent) -> Profile {
+    let by_name = rules.iter().find(|rule| match &rule.matcher {
+        Matcher::Name(name) => name.eq_ignore_ascii_case(&opponent.name),
+        _ => false
+    });
+
+    let by_rating = opponent.elo.and_then(|elo| rules.iter()
+        .filter_map(|rule| match rule.matcher {
+            Matcher::Below(limit) if elo < limit => Some((limit, rule)),
+            _ => None
+        })
+        .min_by_key(|&(limit, _)| limit)
+        .map(|(_, rule)| rule));
+
+    let default = rules.iter().find(|rule| rule.matcher == Matcher::Default);
+
+    by_name.or(by_rating).or(default).map_or(DEFAULT_PROFILE, |rule| rule.profile)
+}
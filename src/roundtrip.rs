@@ -0,0 +1,44 @@
+use chessing::{chess::Chess, game::GameTemplate};
+
+use crate::{fen::to_fen, san::parse_uci, search::legal_actions, util::Rng, variants::VARIANTS};
+
+pub const ROUNDTRIP_GAMES: usize = 100;
+pub const ROUNDTRIP_PLIES: usize = 200;
+
+// A move that doesn't come back from its UCI name gets played as something else (or not at all)
+// by the GUI, forfeiting the game. Games start from the bench positions so castling, en passant
+// and promotions all come up
+pub fn roundtrip(games: usize, seed: u64) -> bool {
+    let variant = &VARIANTS[0];
+    let chess = Chess::create::<u64, 6>();
+    let mut rng = Rng::new(seed);
+
+    let mut checked = 0;
+    let mut failures = 0;
+
+    for game in 0..games {
+        let mut board = chess.load(variant.positions[game % variant.positions.len()]);
+
+        for ply in 0..ROUNDTRIP_PLIES {
+            let actions = legal_actions(&mut board);
+            if actions.is_empty() {
+                break;
+            }
+
+            for &action in &actions {
+                let name = board.display_uci_action(action);
+                checked += 1;
+
+                if parse_uci(&mut board, &name) != Some(action) {
+                    failures += 1;
+                    println!("info string {} doesn't round trip in {}", name, to_fen(&board, ply / 2 + 1));
+                }
+            }
+
+            board.play(actions[rng.below(actions.len())]);
+        }
+    }
+
+    println!("info string checked {} moves over {} games, {} failed", checked, games, failures);
+    failures == 0
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use super::engine::{EngineConfig, UciEngine, HANDSHAKE_TIMEOUT};
+
+// Long enough that the commands sent after `go` arrive mid-search
+const SEARCH_TIME: u64 = 1000;
+// Queen and king only, so the second search's move has to start from e1 or e2
+const SECOND_POSITION: &str = "8/8/8/4k3/8/8/4K3/4Q3 w - - 0 1";
+
+fn bestmove_timeout() -> Duration {
+    Duration::from_millis(SEARCH_TIME) + HANDSHAKE_TIMEOUT
+}
+
+// Unknown commands get no answer at all, so the next thing said is `readyok`
+fn unknown_command_is_silent(engine: &mut UciEngine) -> Result<(), String> {
+    engine.send("xyzzy");
+    engine.send("isready");
+
+    match engine.read_line(HANDSHAKE_TIMEOUT) {
+        Some(line) if line.trim() == "readyok" => Ok(()),
+        Some(line) => Err(format!("answered an unknown command with `{}`", line)),
+        None => Err("stopped answering after an unknown command".to_string())
+    }
+}
+
+fn isready_during_search(engine: &mut UciEngine) -> Result<(), String> {
+    engine.send("position startpos");
+    engine.send(&format!("go movetime {}", SEARCH_TIME));
+    engine.send("isready");
+
+    loop {
+        match engine.read_line(bestmove_timeout()) {
+            Some(line) if line.trim() == "readyok" => return Ok(()),
+            Some(line) if line.starts_with("bestmove") => return Err("only answered `isready` after the search".to_string()),
+            Some(_) => {}
+            None => return Err("never answered `isready`".to_string())
+        }
+    }
+}
+
+// Follows on from `isready_during_search`, whose search is still running
+fn position_and_go_deferred(engine: &mut UciEngine) -> Result<(), String> {
+    engine.send(&format!("position fen {}", SECOND_POSITION));
+    engine.send("go movetime 100");
+
+    let first = engine.wait_for("bestmove", bestmove_timeout()).ok_or("the first search never finished")?;
+    let second = engine.wait_for("bestmove", bestmove_timeout()).ok_or("the second search never ran")?;
+
+    let second_move = second.split_whitespace().nth(1).unwrap_or("");
+    if !second_move.starts_with("e1") && !second_move.starts_with("e2") {
+        return Err(format!("searched the wrong position, got `{}` after `{}`", second, first));
+    }
+
+    Ok(())
+}
+
+// Drives an engine (by default this one) through the parts of UCI that trip up GUIs,
+// with `StrictUCI` on
+pub fn conformance(args: &[&str]) {
+    let command = match args.first() {
+        Some(command) => command.to_string(),
+        None => match std::env::current_exe() {
+            Ok(path) => path.display().to_string(),
+            Err(error) => {
+                println!("info string can't find the engine binary: {}", error);
+                return;
+            }
+        }
+    };
+
+    let config = EngineConfig {
+        command,
+        name: None,
+        options: vec![ ("StrictUCI".to_string(), "true".to_string()) ],
+        limit: None
+    };
+
+    let mut engine = match UciEngine::start(&config) {
+        Ok(engine) => engine,
+        Err(error) => {
+            println!("info string {}", error);
+            return;
+        }
+    };
+
+    let checks: [(&str, fn(&mut UciEngine) -> Result<(), String>); 3] = [
+        ("unknown command is silent", unknown_command_is_silent),
+        ("isready during search", isready_during_search),
+        ("position and go deferred", position_and_go_deferred)
+    ];
+
+    let mut failures = 0;
+    for (name, check) in checks {
+        match check(&mut engine) {
+            Ok(()) => println!("{:<28} ok", name),
+            Err(error) => {
+                println!("{:<28} FAILED: {}", name, error);
+                failures += 1;
+            }
+        }
+    }
+
+    println!("{}: {}/{} checks passed", engine.name, checks.len() - failures, checks.len());
+}
@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+use super::{sprt::{elo, games, llr, llr_bounds, Results, SprtBounds}, Termination};
+
+pub const LLR_BAR_WIDTH: usize = 40;
+
+// Console summary of a running pairing, redrawn every `interval`
+pub struct Dashboard {
+    pub started: Instant,
+    pub last_refresh: Instant,
+    pub interval: Duration,
+    pub time_losses: u64,
+    pub illegal_moves: u64,
+    pub crashes: u64,
+    pub stalls: u64,
+    pub adjudications: u64
+}
+
+pub fn create_dashboard(interval: Duration) -> Dashboard {
+    Dashboard {
+        started: Instant::now(),
+        last_refresh: Instant::now(),
+        interval,
+        time_losses: 0,
+        illegal_moves: 0,
+        crashes: 0,
+        stalls: 0,
+        adjudications: 0
+    }
+}
+
+pub fn record_termination(dashboard: &mut Dashboard, termination: Termination) {
+    match termination {
+        Termination::TimeLoss => dashboard.time_losses += 1,
+        Termination::IllegalMove => dashboard.illegal_moves += 1,
+        Termination::Crash => dashboard.crashes += 1,
+        Termination::Stall => dashboard.stalls += 1,
+        Termination::Adjudication => dashboard.adjudications += 1,
+        Termination::Normal => {}
+    }
+}
+
+// Where the LLR sits between the bounds, ie. `-2.94 [=========|.........] 2.94`
+pub fn llr_bar(llr: f64, lower: f64, upper: f64) -> String {
+    let progress = ((llr - lower) / (upper - lower)).clamp(0., 1.);
+    let filled = (progress * LLR_BAR_WIDTH as f64).round() as usize;
+    let middle = LLR_BAR_WIDTH / 2;
+
+    let bar: String = (0..LLR_BAR_WIDTH)
+        .map(|i| if i == middle { '|' } else if i < filled { '=' } else { '.' })
+        .collect();
+
+    format!("{:.2} [{}] {:.2}", lower, bar, upper)
+}
+
+pub fn should_refresh(dashboard: &Dashboard) -> bool {
+    dashboard.last_refresh.elapsed() >= dashboard.interval
+}
+
+pub fn render(
+    dashboard: &mut Dashboard,
+    results: &Results,
+    names: (&str, &str),
+    total_games: u64,
+    bounds: Option<&SprtBounds>
+) -> String {
+    dashboard.last_refresh = Instant::now();
+
+    let played = games(results);
+    let score = (results.wins as f64 + results.draws as f64 / 2.) / played.max(1) as f64;
+    let seconds = dashboard.started.elapsed().as_secs().max(1);
+
+    let mut lines = vec![
+        format!("{} vs {}", names.0, names.1),
+        format!(
+            "  Games: {}/{}  +{} -{} ={}  Elo: {:.1}  ({:.1} games/min)",
+            played, total_games, results.wins, results.losses, results.draws, elo(score),
+            played as f64 * 60. / seconds as f64
+        ),
+        format!(
+            "  Time losses: {}  Illegal moves: {}  Crashes: {}  Stalls: {}  Adjudicated: {}",
+            dashboard.time_losses, dashboard.illegal_moves, dashboard.crashes, dashboard.stalls, dashboard.adjudications
+        )
+    ];
+
+    if let Some(bounds) = bounds {
+        let (lower, upper) = llr_bounds(bounds);
+        lines.push(format!("  LLR: {:>6.2}  {}", llr(results, bounds), llr_bar(llr(results, bounds), lower, upper)));
+    }
+
+    lines.join("\n")
+}
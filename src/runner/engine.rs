@@ -0,0 +1,172 @@
+use std::{collections::VecDeque, io::{BufRead, BufReader, Write}, process::{Child, ChildStdin, Command, Stdio}, sync::mpsc::{self, Receiver, RecvTimeoutError}, thread, time::{Duration, Instant}};
+
+use super::Limit;
+
+// How long an engine gets to answer `uci` and `isready`
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// Recent UCI traffic kept per engine, saved when a game goes wrong
+pub const LOG_LINES: usize = 500;
+
+// How to launch an engine and what to configure once it's up
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub command: String,
+    // Overrides the name the engine reports in `id name`
+    pub name: Option<String>,
+    pub options: Vec<(String, String)>,
+    // Replaces the match time control for this engine, for odds matches
+    pub limit: Option<Limit>
+}
+
+// A UCI engine running as a subprocess, read from a background thread so
+// the runner can stop waiting on an engine that has run out of time
+pub struct UciEngine {
+    pub name: String,
+    pub config: EngineConfig,
+    pub log: VecDeque<String>,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>
+}
+
+impl UciEngine {
+    pub fn start(config: &EngineConfig) -> Result<UciEngine, String> {
+        let path = &config.command;
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("failed to start {}: {}", path, error))?;
+
+        let stdin = child.stdin.take().expect("Stdin is piped");
+        let stdout = child.stdout.take().expect("Stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            name: path.to_string(),
+            config: config.clone(),
+            log: VecDeque::with_capacity(LOG_LINES),
+            child,
+            stdin,
+            lines
+        };
+
+        engine.send("uci");
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        let mut supported = vec![];
+        loop {
+            let line = engine.read_line(deadline.saturating_duration_since(Instant::now()))
+                .ok_or_else(|| format!("{} did not answer `uci`", path))?;
+
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            }
+
+            if let Some(option) = line.strip_prefix("option name ") {
+                let name = option.split(" type ").next().unwrap_or(option);
+                supported.push(name.trim().to_ascii_lowercase());
+            }
+
+            if line.trim() == "uciok" {
+                break;
+            }
+        }
+
+        if let Some(name) = &config.name {
+            engine.name = name.clone();
+        }
+
+        // Option names are case insensitive in UCI
+        for (name, value) in &config.options {
+            if !supported.contains(&name.to_ascii_lowercase()) {
+                println!("info string {} has no option {}", engine.name, name);
+            }
+
+            engine.send(&format!("setoption name {} value {}", name, value));
+        }
+
+        engine.ready()?;
+
+        Ok(engine)
+    }
+
+    // Crashed engines are replaced with a fresh process so the match can go on
+    pub fn restart(&mut self) -> Result<(), String> {
+        let config = self.config.clone();
+        *self = UciEngine::start(&config)?;
+
+        Ok(())
+    }
+
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn log_line(&mut self, line: String) {
+        if self.log.len() >= LOG_LINES {
+            self.log.pop_front();
+        }
+
+        self.log.push_back(line);
+    }
+
+    pub fn send(&mut self, command: &str) -> bool {
+        self.log_line(format!("> {}", command));
+
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .is_ok()
+    }
+
+    // `None` if the engine didn't say anything in time or has exited
+    pub fn read_line(&mut self, timeout: Duration) -> Option<String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => {
+                self.log_line(format!("< {}", line));
+                Some(line)
+            }
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None
+        }
+    }
+
+    pub fn wait_for(&mut self, prefix: &str, timeout: Duration) -> Option<String> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let line = self.read_line(deadline.saturating_duration_since(Instant::now()))?;
+            if line.starts_with(prefix) {
+                return Some(line);
+            }
+        }
+    }
+
+    pub fn ready(&mut self) -> Result<(), String> {
+        self.send("isready");
+        self.wait_for("readyok", HANDSHAKE_TIMEOUT)
+            .map(|_| ())
+            .ok_or_else(|| format!("{} did not answer `isready`", self.name))
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        self.send("quit");
+        thread::sleep(Duration::from_millis(50));
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
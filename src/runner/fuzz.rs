@@ -0,0 +1,108 @@
+use crate::{options::uci_options, util::Rng};
+
+use super::engine::{EngineConfig, UciEngine, HANDSHAKE_TIMEOUT};
+
+pub const FUZZ_COMMANDS: usize = 1000;
+// Traffic shown when the engine dies, enough to see the commands leading up to it
+const SHOWN_LOG_LINES: usize = 20;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const FEN_CHARS: &str = "pnbrqkPNBRQK0123456789/ wb-";
+const MOVES: [&str; 8] = [ "e2e4", "e7e5", "g1f3", "b8c6", "e1g1", "a7a8q", "a1a9", "zz" ];
+const WORDS: [&str; 12] = [ "position", "go", "setoption", "name", "value", "moves", "fen", "startpos", "depth", "-1", "99999999999999999999", "" ];
+
+fn pick<'a>(rng: &mut Rng, items: &[&'a str]) -> &'a str {
+    items[rng.below(items.len())]
+}
+
+// The start position with a few characters swapped for others a FEN could hold
+fn mangled_fen(rng: &mut Rng) -> String {
+    let mut fen: Vec<char> = START_FEN.chars().collect();
+    let fen_chars: Vec<char> = FEN_CHARS.chars().collect();
+
+    for _ in 0..rng.below(4) {
+        let index = rng.below(fen.len());
+        fen[index] = fen_chars[rng.below(fen_chars.len())];
+    }
+
+    fen.into_iter().collect()
+}
+
+fn random_moves(rng: &mut Rng) -> String {
+    (0..rng.below(5)).map(|_| pick(rng, &MOVES)).collect::<Vec<_>>().join(" ")
+}
+
+// Every search is bounded, as the fuzzer waits for each command to finish
+fn random_command(rng: &mut Rng) -> String {
+    match rng.below(10) {
+        0 => pick(rng, &[ "uci", "isready", "ucinewgame", "stop", "ponderhit" ]).to_string(),
+        1 => format!("position startpos moves {}", random_moves(rng)),
+        2 => format!("position fen {} moves {}", mangled_fen(rng), random_moves(rng)),
+        3 => format!("go depth {}", rng.below(6)),
+        4 => format!("go nodes {}", rng.below(20_000)),
+        5 => format!("go wtime {} btime {} winc {}", rng.below(2000), rng.below(2000), rng.below(100)),
+        6 => {
+            let options = uci_options();
+            let option = &options[rng.below(options.len())];
+            format!("setoption name {} value {}", option.name, pick(rng, &WORDS))
+        }
+        7 => (0..rng.below(6)).map(|_| pick(rng, &WORDS)).collect::<Vec<_>>().join(" "),
+        8 => (0..rng.below(40)).map(|_| (b' ' + rng.below(95) as u8) as char).collect(),
+        _ => String::new()
+    }
+}
+
+// Feeds an engine (by default this one) a stream of random, mostly malformed UCI commands,
+// checking after each that it's still alive and answering `isready`
+pub fn fuzz(commands: usize, seed: u64, args: &[&str]) -> bool {
+    let command = match args.first() {
+        Some(command) => command.to_string(),
+        None => match std::env::current_exe() {
+            Ok(path) => path.display().to_string(),
+            Err(error) => {
+                println!("info string can't find the engine binary: {}", error);
+                return false;
+            }
+        }
+    };
+
+    let config = EngineConfig { command, name: None, options: vec![], limit: None };
+    let mut engine = match UciEngine::start(&config) {
+        Ok(engine) => engine,
+        Err(error) => {
+            println!("info string {}", error);
+            return false;
+        }
+    };
+
+    let mut rng = Rng::new(seed);
+    let mut failures = 0;
+
+    for number in 0..commands {
+        let command = random_command(&mut rng);
+        engine.send(&command);
+        engine.send("isready");
+
+        if engine.wait_for("readyok", HANDSHAKE_TIMEOUT).is_some() {
+            continue;
+        }
+
+        failures += 1;
+        let state = if engine.is_alive() { "stopped answering" } else { "died" };
+        println!("command {} `{}`: {} {}", number + 1, command, engine.name, state);
+
+        let skip = engine.log.len().saturating_sub(SHOWN_LOG_LINES);
+        for line in engine.log.iter().skip(skip) {
+            println!("    {}", line);
+        }
+
+        if let Err(error) = engine.restart() {
+            println!("info string {}", error);
+            return false;
+        }
+    }
+
+    println!("{}: {} commands, {} failures", engine.name, commands, failures);
+
+    failures == 0
+}
@@ -0,0 +1,572 @@
+use std::{env, time::{Duration, Instant}};
+
+use chessing::{bitboard::{BitBoard, BitInt}, chess::Chess, game::{zobrist::ZobristTable, Board, GameState, GameTemplate, Team}};
+pub use conformance::conformance;
+pub use fuzz::{fuzz, FUZZ_COMMANDS};
+use dashboard::{create_dashboard, record_termination, render, should_refresh};
+use engine::{EngineConfig, UciEngine};
+use openings::{load_openings, Opening};
+use output::{save_failed_game, write_results, GameRecord};
+use ratings::{leaderboard, load_ratings, update_ratings, write_ratings, Rating};
+use sprt::{elo, llr, llr_bounds, record_game, record_pair, report, Results, SprtBounds};
+
+use crate::{search::legal_actions, signals::shutdown_requested};
+
+mod conformance;
+mod dashboard;
+mod engine;
+mod fuzz;
+mod openings;
+mod output;
+mod ratings;
+mod sprt;
+
+// Slack on top of the clock before an engine is considered to have lost on time
+pub const TIME_MARGIN: u64 = 100;
+pub const MAX_GAME_PLIES: usize = 600;
+// Node limited engines have no clock, but still shouldn't hang a match
+pub const NODES_MOVE_TIMEOUT: u64 = 60_000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct TimeControl {
+    pub base: u64,
+    pub inc: u64
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Limit {
+    Clock(TimeControl),
+    Nodes(u64)
+}
+
+pub struct MatchOptions {
+    // The first engine plays a gauntlet against every other one
+    pub engines: Vec<EngineConfig>,
+    pub games: u64,
+    pub tc: TimeControl,
+    pub sprt: Option<SprtBounds>,
+    pub report_every: u64,
+    // EPD or PGN, each opening is played once with either color
+    pub openings: Option<String>,
+    pub dashboard_interval: u64,
+    // CSV, or JSON if the path ends in `.json`
+    pub results_file: Option<String>,
+    // Where PGNs and engine logs of crashed or forfeited games go
+    pub errors_dir: Option<String>,
+    // JSON ratings carried over between matches, updated after every game
+    pub ratings_file: Option<String>
+}
+
+// How a game ended, beyond the result itself
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Normal,
+    Adjudication,
+    TimeLoss,
+    IllegalMove,
+    // The engine process exited
+    Crash,
+    // The engine stopped responding without a clock to lose on
+    Stall
+}
+
+pub struct GameResult {
+    // Index of the winning engine, if any
+    pub winner: Option<usize>,
+    pub termination: Termination,
+    pub reason: String
+}
+
+// `10+0.1` style, in seconds
+pub fn parse_tc(tc: &str) -> Option<TimeControl> {
+    let (base, inc) = tc.split_once('+').unwrap_or((tc, "0"));
+    let base: f64 = base.parse().ok()?;
+    let inc: f64 = inc.parse().ok()?;
+
+    Some(TimeControl {
+        base: (base * 1000.) as u64,
+        inc: (inc * 1000.) as u64
+    })
+}
+
+pub fn display_limit(limit: &Limit) -> String {
+    match limit {
+        Limit::Clock(tc) => format!("{}+{}", tc.base as f64 / 1000., tc.inc as f64 / 1000.),
+        Limit::Nodes(nodes) => format!("{} nodes", nodes)
+    }
+}
+
+fn engine_limit(config: &EngineConfig, options: &MatchOptions) -> Limit {
+    config.limit.unwrap_or(Limit::Clock(options.tc))
+}
+
+// match engine <path> [name <name>] [option.<Name>=<value>].. [tc=<base+inc>] [nodes=<n>] engine <path> .. [games <n>] [openings <file>] [dashboard <secs>] [results <file>] [errors <dir>] [ratings <file>] [tc <base+inc>] [sprt <elo0> <elo1>] [alpha <a>] [beta <b>]
+pub fn parse_match_options(args: &[&str]) -> MatchOptions {
+    let mut options = MatchOptions {
+        engines: vec![],
+        games: 1000,
+        tc: TimeControl { base: 10_000, inc: 100 },
+        sprt: None,
+        report_every: 20,
+        openings: None,
+        dashboard_interval: 10,
+        results_file: None,
+        errors_dir: None,
+        ratings_file: None
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1).copied().unwrap_or("");
+
+        match args[i] {
+            "engine" => {
+                options.engines.push(EngineConfig { command: value.to_string(), name: None, options: vec![], limit: None });
+                i += 1;
+            }
+            "name" => {
+                if let Some(engine) = options.engines.last_mut() {
+                    engine.name = Some(value.to_string());
+                }
+                i += 1;
+            }
+            "games" => {
+                // Rounded up to whole pairs
+                let games: u64 = value.parse().unwrap_or(options.games);
+                options.games = games + games % 2;
+                i += 1;
+            }
+            "tc" => {
+                if let Some(tc) = parse_tc(value) {
+                    options.tc = tc;
+                }
+                i += 1;
+            }
+            "openings" => {
+                options.openings = Some(value.to_string());
+                i += 1;
+            }
+            "dashboard" => {
+                options.dashboard_interval = value.parse().unwrap_or(options.dashboard_interval);
+                i += 1;
+            }
+            "results" => {
+                options.results_file = Some(value.to_string());
+                i += 1;
+            }
+            "errors" => {
+                options.errors_dir = Some(value.to_string());
+                i += 1;
+            }
+            "ratings" => {
+                options.ratings_file = Some(value.to_string());
+                i += 1;
+            }
+            "report" => {
+                options.report_every = value.parse().unwrap_or(options.report_every).max(2);
+                i += 1;
+            }
+            "sprt" => {
+                let elo0 = value.parse().unwrap_or(0.);
+                let elo1 = args.get(i + 2).and_then(|elo| elo.parse().ok()).unwrap_or(5.);
+
+                options.sprt = Some(SprtBounds { elo0, elo1, alpha: 0.05, beta: 0.05 });
+                i += 2;
+            }
+            "alpha" | "beta" => {
+                if let (Some(bounds), Ok(probability)) = (&mut options.sprt, value.parse()) {
+                    if args[i] == "alpha" {
+                        bounds.alpha = probability;
+                    } else {
+                        bounds.beta = probability;
+                    }
+                }
+                i += 1;
+            }
+            arg => {
+                // Options and limits apply to the engine declared most recently
+                if let Some(engine) = options.engines.last_mut() {
+                    if let Some((name, value)) = arg.strip_prefix("option.").and_then(|option| option.split_once('=')) {
+                        engine.options.push((name.to_string(), value.to_string()));
+                    } else if let Some(tc) = arg.strip_prefix("tc=").and_then(parse_tc) {
+                        engine.limit = Some(Limit::Clock(tc));
+                    } else if let Some(nodes) = arg.strip_prefix("nodes=").and_then(|nodes| nodes.parse().ok()) {
+                        engine.limit = Some(Limit::Nodes(nodes));
+                    }
+                }
+            }
+        }
+
+        i += 1;
+    }
+
+    // Without explicit engines, play this binary against itself
+    while options.engines.len() < 2 {
+        let current = env::current_exe()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| "artifact".to_string());
+        options.engines.push(EngineConfig { command: current, name: None, options: vec![], limit: None });
+    }
+
+    options
+}
+
+// Plays the opening's moves, returning the hashes of the positions before each; `None` if one
+// of them isn't legal, so the opening can be skipped instead of stopping the match
+fn play_opening<T: BitInt, const N: usize>(
+    board: &mut Board<T, N>,
+    zobrist: &ZobristTable,
+    opening: &Opening,
+    moves: &mut Vec<String>
+) -> Option<Vec<u64>> {
+    let mut hashes = vec![];
+
+    for uci_move in &opening.moves {
+        let action = legal_actions(board)
+            .into_iter()
+            .find(|&action| board.display_uci_action(action) == *uci_move)?;
+
+        hashes.push(board.game.rules.hash(board, zobrist));
+        board.play(action);
+        moves.push(uci_move.clone());
+    }
+
+    Some(hashes)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_game<T: BitInt, const N: usize>(
+    board: &mut Board<T, N>,
+    zobrist: &ZobristTable,
+    engines: &mut [&mut UciEngine; 2],
+    opening: &Opening,
+    white: usize,
+    limits: &[Limit; 2],
+    moves: &mut Vec<String>,
+    mut hashes: Vec<u64>
+) -> GameResult {
+    let position = match &opening.fen {
+        Some(fen) => format!("position fen {}", fen),
+        None => "position startpos".to_string()
+    };
+
+    let mut clocks = limits.map(|limit| match limit {
+        Limit::Clock(tc) => tc.base,
+        Limit::Nodes(_) => 0
+    });
+    let increments = limits.map(|limit| match limit {
+        Limit::Clock(tc) => tc.inc,
+        Limit::Nodes(_) => 0
+    });
+    let mut halfmove_clock = 0;
+
+    for (index, engine) in engines.iter_mut().enumerate() {
+        engine.send("ucinewgame");
+
+        // An engine that can't start the game forfeits it
+        if engine.ready().is_err() {
+            let termination = if engine.is_alive() { Termination::Stall } else { Termination::Crash };
+            return GameResult { winner: Some(1 - index), termination, reason: format!("{} is not responding", engine.name) };
+        }
+    }
+
+    loop {
+        let actions = legal_actions(board);
+        let mover = if board.state.moving_team == Team::White { white } else { 1 - white };
+
+        match board.game_state(&actions) {
+            GameState::Win(team) => {
+                let winner = if team == Team::White { white } else { 1 - white };
+                return GameResult { winner: Some(winner), termination: Termination::Normal, reason: "checkmate".to_string() };
+            }
+            GameState::Draw => {
+                return GameResult { winner: None, termination: Termination::Normal, reason: "draw".to_string() };
+            }
+            GameState::Ongoing => {}
+        }
+
+        let hash = board.game.rules.hash(board, zobrist);
+        if hashes.iter().filter(|&&previous| previous == hash).count() >= 2 {
+            return GameResult { winner: None, termination: Termination::Normal, reason: "threefold repetition".to_string() };
+        }
+        if halfmove_clock >= 100 {
+            return GameResult { winner: None, termination: Termination::Normal, reason: "fifty moves".to_string() };
+        }
+        if moves.len() >= MAX_GAME_PLIES {
+            return GameResult { winner: None, termination: Termination::Adjudication, reason: "game too long".to_string() };
+        }
+
+        let engine = &mut engines[mover];
+
+        if moves.is_empty() {
+            engine.send(&position);
+        } else {
+            engine.send(&format!("{} moves {}", position, moves.join(" ")));
+        }
+
+        let allowed = match limits[mover] {
+            Limit::Clock(_) => {
+                engine.send(&format!(
+                    "go wtime {} btime {} winc {} binc {}",
+                    clocks[white], clocks[1 - white], increments[white], increments[1 - white]
+                ));
+                clocks[mover] + TIME_MARGIN
+            }
+            Limit::Nodes(nodes) => {
+                engine.send(&format!("go nodes {}", nodes));
+                NODES_MOVE_TIMEOUT
+            }
+        };
+
+        let start = Instant::now();
+        let response = engine.wait_for("bestmove", Duration::from_millis(allowed));
+        let elapsed = start.elapsed().as_millis() as u64;
+
+        let line = match response {
+            Some(line) if elapsed <= allowed => line,
+            _ if !engine.is_alive() => {
+                return GameResult { winner: Some(1 - mover), termination: Termination::Crash, reason: format!("{} crashed", engine.name) };
+            }
+            _ if matches!(limits[mover], Limit::Nodes(_)) => {
+                engine.send("stop");
+                return GameResult { winner: Some(1 - mover), termination: Termination::Stall, reason: format!("{} stalled", engine.name) };
+            }
+            _ => {
+                engine.send("stop");
+                return GameResult { winner: Some(1 - mover), termination: Termination::TimeLoss, reason: format!("{} loses on time", engine.name) };
+            }
+        };
+
+        if let Limit::Clock(_) = limits[mover] {
+            clocks[mover] = clocks[mover].saturating_sub(elapsed) + increments[mover];
+        }
+
+        let uci_move = line.split_whitespace().nth(1).unwrap_or("");
+        let action = match actions.iter().find(|&&action| board.display_uci_action(action) == uci_move) {
+            Some(&action) => action,
+            None => {
+                return GameResult { winner: Some(1 - mover), termination: Termination::IllegalMove, reason: format!("{} played illegal move {}", engine.name, uci_move) };
+            }
+        };
+
+        // Pawn moves and captures reset the fifty move counter
+        let is_capture = BitBoard::index(action.to).and(board.state.opposite_team()).is_set();
+        halfmove_clock = if action.piece == 0 || is_capture { 0 } else { halfmove_clock + 1 };
+
+        hashes.push(hash);
+        board.play(action);
+        moves.push(uci_move.to_string());
+    }
+}
+
+// Plays one engine pairing until the game count runs out or SPRT concludes
+fn run_pairing(
+    engines: &mut [&mut UciEngine; 2],
+    limits: [Limit; 2],
+    openings: &[Opening],
+    options: &MatchOptions,
+    records: &mut Vec<GameRecord>,
+    ratings: &mut Vec<Rating>
+) -> Results {
+    let names = (engines[0].name.clone(), engines[1].name.clone());
+
+    // Odds matches show both limits
+    let (first, second) = (display_limit(&limits[0]), display_limit(&limits[1]));
+    let limit_display = if first == second { first } else { format!("{} vs {}", first, second) };
+    let chess = Chess::create::<u64, 6>();
+
+    let mut results = Results::default();
+    let mut first_of_pair = 0;
+    let mut dashboard = create_dashboard(Duration::from_secs(options.dashboard_interval));
+
+    for game in 0..options.games {
+        // Consecutive games form a pair, playing the same opening with colors swapped
+        let opening_index = (game / 2) as usize % openings.len();
+        let opening = &openings[opening_index];
+        let white = (game % 2) as usize;
+
+        let mut board = match &opening.fen {
+            Some(fen) => chess.load(fen),
+            None => chess.default()
+        };
+        let zobrist = board.game.rules.gen_zobrist(&mut board, 64);
+
+        let mut moves = vec![];
+        let hashes = match play_opening(&mut board, &zobrist, opening, &mut moves) {
+            Some(hashes) => hashes,
+            None => {
+                println!("info string skipping opening {}, its moves aren't legal", opening_index + 1);
+                continue;
+            }
+        };
+        let result = play_game(&mut board, &zobrist, engines, opening, white, &limits, &mut moves, hashes);
+
+        // Engines share our terminal and likely got the signal too, so the game isn't scored
+        if shutdown_requested() {
+            println!("{}", report(&results, (&names.0, &names.1), &limit_display, options.sprt.as_ref()));
+            println!("Match interrupted after {} games", game);
+            break;
+        }
+
+        let points = match result.winner {
+            Some(0) => 2,
+            Some(_) => 0,
+            None => 1
+        };
+        record_game(&mut results, points);
+
+        if game % 2 == 0 {
+            first_of_pair = points;
+        } else {
+            record_pair(&mut results, first_of_pair, points);
+        }
+
+        record_termination(&mut dashboard, result.termination);
+
+        let (white_name, black_name) = if white == 0 { (&names.0, &names.1) } else { (&names.1, &names.0) };
+        let result_display = match result.winner {
+            Some(winner) if winner == white => "1-0",
+            Some(_) => "0-1",
+            None => "1/2-1/2"
+        };
+
+        println!("Finished game {} {} vs {} ({}): {}", game + 1, white_name, black_name, result.reason, result_display);
+
+        records.push(GameRecord {
+            game: records.len() as u64 + 1,
+            white: white_name.clone(),
+            black: black_name.clone(),
+            opening: opening_index,
+            result: result_display,
+            termination: result.termination,
+            reason: result.reason,
+            plies: moves.len()
+        });
+
+        if let Some(path) = &options.results_file {
+            write_results(path, records);
+        }
+
+        if let Some(path) = &options.ratings_file {
+            update_ratings(ratings, &names.0, &names.1, points);
+            write_ratings(path, ratings);
+        }
+
+        // Forfeits are scored as losses for the offending engine, which gets restarted if it died or hung
+        let record = records.last().expect("Game was recorded");
+        if let (Some(winner), Termination::Crash | Termination::Stall | Termination::IllegalMove) = (result.winner, result.termination) {
+            let offender = 1 - winner;
+
+            if let Some(dir) = &options.errors_dir {
+                save_failed_game(dir, record, opening.fen.as_deref(), &moves, [ &*engines[0], &*engines[1] ]);
+            }
+
+            if result.termination != Termination::IllegalMove {
+                if let Err(error) = engines[offender].restart() {
+                    println!("info string {}, stopping the match", error);
+                    break;
+                }
+            }
+        }
+
+        let finished = match &options.sprt {
+            Some(bounds) if game % 2 == 1 => {
+                let (lower, upper) = llr_bounds(bounds);
+                let llr = llr(&results, bounds);
+                llr <= lower || llr >= upper
+            }
+            _ => false
+        };
+
+        if should_refresh(&dashboard) {
+            println!("{}", render(&mut dashboard, &results, (&names.0, &names.1), options.games, options.sprt.as_ref()));
+        }
+
+        if finished || (game + 1) % options.report_every == 0 || game + 1 == options.games {
+            println!("{}", report(&results, (&names.0, &names.1), &limit_display, options.sprt.as_ref()));
+        }
+
+        if finished {
+            let bounds = options.sprt.as_ref().expect("SPRT is running");
+            let accepted = if llr(&results, bounds) > 0. { "H1" } else { "H0" };
+            println!("SPRT finished, {} was accepted", accepted);
+            break;
+        }
+    }
+
+    results
+}
+
+pub fn run_match(args: &[&str]) {
+    let options = parse_match_options(args);
+
+    let openings = match &options.openings {
+        Some(path) => match load_openings(path) {
+            Ok(openings) => openings,
+            Err(error) => {
+                println!("info string {}", error);
+                return;
+            }
+        },
+        None => vec![ Opening::default() ]
+    };
+
+    let mut ratings = match &options.ratings_file {
+        Some(path) => match load_ratings(path) {
+            Ok(ratings) => ratings,
+            Err(error) => {
+                println!("info string {}", error);
+                return;
+            }
+        },
+        None => vec![]
+    };
+
+    let mut engines = vec![];
+    for config in &options.engines {
+        match UciEngine::start(config) {
+            Ok(engine) => engines.push(engine),
+            Err(error) => {
+                println!("info string {}", error);
+                return;
+            }
+        }
+    }
+
+    let (candidate, opponents) = engines.split_first_mut().expect("Match has engines");
+    let mut gauntlet = vec![];
+    let mut records = vec![];
+
+    let candidate_limit = engine_limit(&options.engines[0], &options);
+
+    for (index, opponent) in opponents.iter_mut().enumerate() {
+        let limits = [ candidate_limit, engine_limit(&options.engines[index + 1], &options) ];
+        let results = run_pairing(&mut [&mut *candidate, &mut *opponent], limits, &openings, &options, &mut records, &mut ratings);
+        gauntlet.push((opponent.name.clone(), results));
+
+        if shutdown_requested() {
+            break;
+        }
+    }
+
+    // A summary is only worth printing once there's more than one opponent
+    if gauntlet.len() > 1 {
+        println!("Gauntlet of {}:", candidate.name);
+        for (name, results) in &gauntlet {
+            let games = results.wins + results.losses + results.draws;
+            let score = (results.wins as f64 + results.draws as f64 / 2.) / games.max(1) as f64;
+
+            println!(
+                "  vs {:<24} {:>6} games  +{} -{} ={}  Elo {:.1}",
+                name, games, results.wins, results.losses, results.draws, elo(score)
+            );
+        }
+    }
+
+    if options.ratings_file.is_some() && !ratings.is_empty() {
+        println!("Ratings:");
+        for line in leaderboard(&ratings) {
+            println!("{}", line);
+        }
+    }
+}
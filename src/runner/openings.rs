@@ -0,0 +1,144 @@
+use std::fs;
+
+use chessing::{chess::Chess, game::GameTemplate};
+
+use crate::{fen::check_fen, san::parse_san};
+
+// A starting position for a game pair, with the moves leading up to it in UCI notation
+#[derive(Clone, Debug, Default)]
+pub struct Opening {
+    pub fen: Option<String>,
+    pub moves: Vec<String>
+}
+
+// EPD lines only carry the first four FEN fields, the move counters are filled in; an invalid
+// position is left out rather than loaded partway through a match
+fn parse_epd(contents: &str) -> Vec<Opening> {
+    let board = Chess::create::<u64, 6>().default();
+
+    contents.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| fields.len() >= 4 && !fields[0].starts_with('#'))
+        .enumerate()
+        .filter_map(|(index, fields)| {
+            let fen = format!("{} 0 1", fields[..4].join(" "));
+
+            match check_fen(&board, &fen) {
+                Ok(()) => Some(Opening { fen: Some(fen), moves: vec![] }),
+                Err(error) => {
+                    println!("info string skipping opening {}, {}", index + 1, error);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+// Splits a PGN into (FEN tag, movetext) per game
+fn pgn_games(contents: &str) -> Vec<(Option<String>, String)> {
+    let mut games = vec![];
+    let mut fen = None;
+    let mut movetext = String::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            // A tag after movetext starts the next game
+            if !movetext.trim().is_empty() {
+                games.push((fen.take(), std::mem::take(&mut movetext)));
+            }
+
+            if let Some(value) = line.strip_prefix("[FEN \"") {
+                fen = value.split('"').next().map(|fen| fen.to_string());
+            }
+        } else {
+            movetext.push_str(line);
+            movetext.push(' ');
+        }
+    }
+
+    if !movetext.trim().is_empty() {
+        games.push((fen, movetext));
+    }
+
+    games
+}
+
+// Strips comments, variations, move numbers, NAGs and results, leaving only SAN moves
+fn san_moves(movetext: &str) -> Vec<String> {
+    let mut stripped = String::new();
+    let mut comment = false;
+    let mut variation = 0;
+
+    for c in movetext.chars() {
+        match c {
+            '{' => comment = true,
+            '}' => comment = false,
+            '(' if !comment => variation += 1,
+            ')' if !comment => variation -= 1,
+            _ if comment || variation > 0 => {}
+            _ => stripped.push(c)
+        }
+    }
+
+    stripped.split_whitespace()
+        .filter(|token| !matches!(*token, "1-0" | "0-1" | "1/2-1/2" | "*"))
+        .map(|token| token.trim_start_matches(|c: char| c.is_ascii_digit()).trim_start_matches('.'))
+        .filter(|token| !token.is_empty() && !token.starts_with('$'))
+        .map(|token| token.to_string())
+        .collect()
+}
+
+fn parse_pgn(contents: &str) -> Vec<Opening> {
+    let chess = Chess::create::<u64, 6>();
+    let start = chess.default();
+    let mut openings = vec![];
+
+    'games: for (index, (fen, movetext)) in pgn_games(contents).into_iter().enumerate() {
+        if let Some(Err(error)) = fen.as_deref().map(|fen| check_fen(&start, fen)) {
+            println!("info string skipping opening {}, {}", index + 1, error);
+            continue;
+        }
+
+        let mut board = match &fen {
+            Some(fen) => chess.load(fen),
+            None => chess.default()
+        };
+
+        let mut moves = vec![];
+        for san in san_moves(&movetext) {
+            match parse_san(&mut board, &san) {
+                Some(action) => {
+                    moves.push(board.display_uci_action(action));
+                    board.play(action);
+                }
+                None => {
+                    println!("info string skipping opening {}, can't read move {}", index + 1, san);
+                    continue 'games;
+                }
+            }
+        }
+
+        openings.push(Opening { fen, moves });
+    }
+
+    openings
+}
+
+pub fn load_openings(path: &str) -> Result<Vec<Opening>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("failed to read openings {}: {}", path, error))?;
+
+    let openings = if path.to_ascii_lowercase().ends_with(".pgn") {
+        parse_pgn(&contents)
+    } else {
+        parse_epd(&contents)
+    };
+
+    if openings.is_empty() {
+        return Err(format!("no openings found in {}", path));
+    }
+
+    Ok(openings)
+}
@@ -0,0 +1,155 @@
+use std::{fs, path::Path};
+
+use chessing::{chess::Chess, game::GameTemplate};
+
+use super::{engine::UciEngine, Termination};
+use crate::{san::display_san, search::legal_actions};
+
+// One finished game, as written to the results file
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    pub game: u64,
+    pub white: String,
+    pub black: String,
+    pub opening: usize,
+    pub result: &'static str,
+    pub termination: Termination,
+    pub reason: String,
+    pub plies: usize
+}
+
+fn termination_name(termination: Termination) -> &'static str {
+    match termination {
+        Termination::Normal => "normal",
+        Termination::Adjudication => "adjudication",
+        Termination::TimeLoss => "time forfeit",
+        Termination::IllegalMove => "illegal move",
+        Termination::Crash => "crash",
+        Termination::Stall => "stall"
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(|c| matches!(c, ',' | '"' | '\n')) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c)
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+fn to_csv(records: &[GameRecord]) -> String {
+    let mut csv = String::from("game,white,black,opening,result,termination,reason,plies\n");
+
+    for record in records {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            record.game, csv_field(&record.white), csv_field(&record.black), record.opening,
+            record.result, termination_name(record.termination), csv_field(&record.reason), record.plies
+        ));
+    }
+
+    csv
+}
+
+fn to_json(records: &[GameRecord]) -> String {
+    let games: Vec<String> = records.iter()
+        .map(|record| format!(
+            "    {{ \"game\": {}, \"white\": {}, \"black\": {}, \"opening\": {}, \"result\": {}, \"termination\": {}, \"reason\": {}, \"plies\": {} }}",
+            record.game, json_string(&record.white), json_string(&record.black), record.opening,
+            json_string(record.result), json_string(termination_name(record.termination)),
+            json_string(&record.reason), record.plies
+        ))
+        .collect();
+
+    format!("{{\n  \"games\": [\n{}\n  ]\n}}\n", games.join(",\n"))
+}
+
+// Rewritten after every game so an interrupted match still leaves a usable file
+pub fn write_results(path: &str, records: &[GameRecord]) {
+    let contents = if path.to_ascii_lowercase().ends_with(".json") {
+        to_json(records)
+    } else {
+        to_csv(records)
+    };
+
+    if let Err(error) = fs::write(path, contents) {
+        println!("info string failed to write results {}: {}", path, error);
+    }
+}
+
+fn to_pgn(record: &GameRecord, fen: Option<&str>, moves: &[String]) -> String {
+    let chess = Chess::create::<u64, 6>();
+    let mut board = match fen {
+        Some(fen) => chess.load(fen),
+        None => chess.default()
+    };
+
+    let mut pgn = format!(
+        "[Event \"artifact match\"]\n[Round \"{}\"]\n[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n[Termination \"{}\"]\n",
+        record.game, record.white, record.black, record.result, record.reason
+    );
+
+    if let Some(fen) = fen {
+        pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", fen));
+    }
+    pgn.push('\n');
+
+    let mut movetext = vec![];
+    for (ply, uci_move) in moves.iter().enumerate() {
+        let action = match legal_actions(&mut board).into_iter().find(|&action| board.display_uci_action(action) == *uci_move) {
+            Some(action) => action,
+            None => break
+        };
+
+        if ply % 2 == 0 {
+            movetext.push(format!("{}.", ply / 2 + 1));
+        }
+        movetext.push(display_san(&mut board, action));
+        board.play(action);
+    }
+    movetext.push(record.result.to_string());
+
+    pgn.push_str(&movetext.join(" "));
+    pgn.push('\n');
+    pgn
+}
+
+// Keeps the game and both engines' recent UCI traffic for debugging
+pub fn save_failed_game(dir: &str, record: &GameRecord, fen: Option<&str>, moves: &[String], engines: [&UciEngine; 2]) {
+    let dir = Path::new(dir);
+    if let Err(error) = fs::create_dir_all(dir) {
+        println!("info string failed to create {}: {}", dir.display(), error);
+        return;
+    }
+
+    let mut files = vec![ (format!("game-{}.pgn", record.game), to_pgn(record, fen, moves)) ];
+    for (index, engine) in engines.iter().enumerate() {
+        let log: Vec<&str> = engine.log.iter().map(|line| line.as_str()).collect();
+        files.push((format!("game-{}-engine-{}.log", record.game, index + 1), format!("# {}\n{}\n", engine.name, log.join("\n"))));
+    }
+
+    for (name, contents) in files {
+        if let Err(error) = fs::write(dir.join(&name), contents) {
+            println!("info string failed to write {}: {}", name, error);
+        }
+    }
+
+    println!("info string saved game {} to {}", record.game, dir.display());
+}
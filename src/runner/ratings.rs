@@ -0,0 +1,138 @@
+use std::{fs, path::Path};
+
+use super::{output::json_string, sprt::score_from_elo};
+
+// Where a configuration nobody has seen yet starts
+pub const INITIAL_RATING: f64 = 1500.;
+// Small, since a gauntlet plays many games between the same few engines
+pub const RATING_K: f64 = 8.;
+
+// One engine's standing in the ratings file, keyed by the name it plays under
+#[derive(Clone, Debug)]
+pub struct Rating {
+    pub name: String,
+    pub elo: f64,
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64
+}
+
+fn create_rating(name: &str) -> Rating {
+    Rating { name: name.to_string(), elo: INITIAL_RATING, wins: 0, losses: 0, draws: 0 }
+}
+
+// Reads back the string starting at `json`'s opening quote
+fn parse_json_string(json: &str) -> Option<String> {
+    let mut chars = json.strip_prefix('"')?.chars();
+    let mut value = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                escaped => value.push(escaped)
+            },
+            c => value.push(c)
+        }
+    }
+
+    None
+}
+
+fn field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(&format!("\"{}\":", key))? + key.len() + 3;
+    Some(line[start..].trim_start())
+}
+
+fn parse_number<T: std::str::FromStr>(line: &str, key: &str) -> Option<T> {
+    let value = field(line, key)?;
+    let end = value.find(|c: char| c == ',' || c == '}' || c.is_whitespace()).unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+fn parse_rating(line: &str) -> Option<Rating> {
+    Some(Rating {
+        name: parse_json_string(field(line, "name")?)?,
+        elo: parse_number(line, "elo")?,
+        wins: parse_number(line, "wins")?,
+        losses: parse_number(line, "losses")?,
+        draws: parse_number(line, "draws")?
+    })
+}
+
+// Only reads the layout `write_ratings` produces, one engine per line
+pub fn load_ratings(path: &str) -> Result<Vec<Rating>, String> {
+    if !Path::new(path).exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let mut ratings = vec![];
+
+    for (number, line) in contents.lines().enumerate().filter(|(_, line)| line.contains("\"name\":")) {
+        match parse_rating(line) {
+            Some(rating) => ratings.push(rating),
+            None => return Err(format!("{}:{}: malformed rating", path, number + 1))
+        }
+    }
+
+    Ok(ratings)
+}
+
+// Sorted best first, so the file reads as a leaderboard
+pub fn write_ratings(path: &str, ratings: &mut [Rating]) {
+    ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+
+    let entries: Vec<String> = ratings.iter()
+        .map(|rating| format!(
+            "    {{ \"name\": {}, \"elo\": {:.1}, \"wins\": {}, \"losses\": {}, \"draws\": {} }}",
+            json_string(&rating.name), rating.elo, rating.wins, rating.losses, rating.draws
+        ))
+        .collect();
+
+    let contents = format!("{{\n  \"ratings\": [\n{}\n  ]\n}}\n", entries.join(",\n"));
+    if let Err(error) = fs::write(path, contents) {
+        println!("info string failed to write ratings {}: {}", path, error);
+    }
+}
+
+fn rating_index(ratings: &mut Vec<Rating>, name: &str) -> usize {
+    match ratings.iter().position(|rating| rating.name == name) {
+        Some(index) => index,
+        None => {
+            ratings.push(create_rating(name));
+            ratings.len() - 1
+        }
+    }
+}
+
+// `points` are the first engine's half-points, as in `Results`
+pub fn update_ratings(ratings: &mut Vec<Rating>, first: &str, second: &str, points: u8) {
+    let (a, b) = (rating_index(ratings, first), rating_index(ratings, second));
+    let score = points as f64 / 2.;
+    let change = RATING_K * (score - score_from_elo(ratings[a].elo - ratings[b].elo));
+
+    ratings[a].elo += change;
+    ratings[b].elo -= change;
+
+    match points {
+        2 => { ratings[a].wins += 1; ratings[b].losses += 1; }
+        1 => { ratings[a].draws += 1; ratings[b].draws += 1; }
+        _ => { ratings[a].losses += 1; ratings[b].wins += 1; }
+    }
+}
+
+pub fn leaderboard(ratings: &[Rating]) -> Vec<String> {
+    ratings.iter()
+        .enumerate()
+        .map(|(rank, rating)| format!(
+            "{:>3}. {:<24} {:>7.1}  +{} -{} ={}",
+            rank + 1, rating.name, rating.elo, rating.wins, rating.losses, rating.draws
+        ))
+        .collect()
+}
@@ -0,0 +1,158 @@
+// Game results are tracked in half-points (0, 1, 2) from the first engine's point of view
+#[derive(Clone, Debug, Default)]
+pub struct Results {
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+    // Pair scores 0, 0.5, 1, 1.5 and 2
+    pub pentanomial: [u64; 5],
+    // The two ways of scoring 1 in a pair
+    pub win_loss_pairs: u64,
+    pub draw_draw_pairs: u64
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SprtBounds {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64
+}
+
+pub fn record_game(results: &mut Results, points: u8) {
+    match points {
+        2 => results.wins += 1,
+        1 => results.draws += 1,
+        _ => results.losses += 1
+    }
+}
+
+pub fn record_pair(results: &mut Results, first: u8, second: u8) {
+    results.pentanomial[(first + second) as usize] += 1;
+
+    if first + second == 2 {
+        if first == 1 {
+            results.draw_draw_pairs += 1;
+        } else {
+            results.win_loss_pairs += 1;
+        }
+    }
+}
+
+pub fn games(results: &Results) -> u64 {
+    results.wins + results.losses + results.draws
+}
+
+pub fn pairs(results: &Results) -> u64 {
+    results.pentanomial.iter().sum()
+}
+
+pub fn elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1. - 1e-6);
+    -400. * (1. / score - 1.).log10()
+}
+
+pub fn score_from_elo(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+// Abramowitz and Stegun 7.1.26
+fn erf(x: f64) -> f64 {
+    let t = 1. / (1. + 0.3275911 * x.abs());
+    let y = 1. - (((((1.061405429 * t - 1.453152027) * t) + 1.421413741) * t - 0.284496736) * t + 0.254829592) * t * (-x * x).exp();
+
+    if x >= 0. { y } else { -y }
+}
+
+// Mean game score and the variance of the per-pair average
+fn pentanomial_stats(results: &Results) -> (f64, f64, f64) {
+    let pairs = pairs(results) as f64;
+    if pairs == 0. {
+        return (0.5, 0., 0.);
+    }
+
+    let mean = results.pentanomial.iter()
+        .enumerate()
+        .map(|(i, &count)| count as f64 * i as f64 / 4.)
+        .sum::<f64>() / pairs;
+
+    let variance = results.pentanomial.iter()
+        .enumerate()
+        .map(|(i, &count)| count as f64 * (i as f64 / 4. - mean).powi(2))
+        .sum::<f64>() / pairs;
+
+    (mean, variance, pairs)
+}
+
+pub fn llr(results: &Results, bounds: &SprtBounds) -> f64 {
+    let (mean, variance, pairs) = pentanomial_stats(results);
+    if variance <= 0. {
+        return 0.;
+    }
+
+    let score0 = score_from_elo(bounds.elo0);
+    let score1 = score_from_elo(bounds.elo1);
+
+    pairs * (score1 - score0) * (2. * mean - score0 - score1) / (2. * variance)
+}
+
+pub fn llr_bounds(bounds: &SprtBounds) -> (f64, f64) {
+    (
+        (bounds.beta / (1. - bounds.alpha)).ln(),
+        ((1. - bounds.beta) / bounds.alpha).ln()
+    )
+}
+
+// Same layout as fastchess, so results can be compared side by side
+pub fn report(results: &Results, names: (&str, &str), tc: &str, bounds: Option<&SprtBounds>) -> String {
+    const Z: f64 = 1.959964;
+
+    let (mean, variance, pairs) = pentanomial_stats(results);
+    let games = games(results);
+
+    let margin = Z * (variance / pairs.max(1.)).sqrt();
+    let elo_value = elo(mean);
+    let elo_error = (elo(mean + margin) - elo(mean - margin)) / 2.;
+
+    let nelo_scale = 800. / 10f64.ln();
+    let nelo = if variance > 0. { (mean - 0.5) / (2. * variance).sqrt() * nelo_scale } else { 0. };
+    let nelo_error = Z * nelo_scale / (2. * pairs.max(1.)).sqrt();
+
+    let los = if variance > 0. {
+        0.5 * (1. + erf((mean - 0.5) / (2. * variance / pairs).sqrt()))
+    } else {
+        0.5
+    };
+
+    let points = results.wins as f64 + results.draws as f64 / 2.;
+    let [ ll, ld, even, wd, ww ] = results.pentanomial;
+
+    let mut lines = vec![
+        "-".repeat(50),
+        format!("Results of {} vs {} ({}):", names.0, names.1, tc),
+        format!("Elo: {:.2} +/- {:.2}, nElo: {:.2} +/- {:.2}", elo_value, elo_error, nelo, nelo_error),
+        format!(
+            "LOS: {:.2} %, DrawRatio: {:.2} %, PairsRatio: {:.2}",
+            los * 100., 100. * results.draws as f64 / games.max(1) as f64, (ww + wd) as f64 / (ll + ld).max(1) as f64
+        ),
+        format!(
+            "Games: {}, Wins: {}, Losses: {}, Draws: {}, Points: {:.1} ({:.2} %)",
+            games, results.wins, results.losses, results.draws, points, 100. * points / games.max(1) as f64
+        ),
+        format!(
+            "Ptnml(0-2): [{}, {}, {}, {}, {}], WL/DD Ratio: {:.2}",
+            ll, ld, even, wd, ww, results.win_loss_pairs as f64 / results.draw_draw_pairs.max(1) as f64
+        )
+    ];
+
+    if let Some(bounds) = bounds {
+        let (lower, upper) = llr_bounds(bounds);
+        lines.push(format!(
+            "LLR: {:.2} ({:.2}, {:.2}) [{:.2}, {:.2}]",
+            llr(results, bounds), lower, upper, bounds.elo0, bounds.elo1
+        ));
+    }
+
+    lines.push("-".repeat(50));
+    lines.join("\n")
+}
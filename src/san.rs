@@ -0,0 +1,229 @@
+use chessing::{bitboard::{BitBoard, BitInt}, game::{action::Action, Board, Team}};
+
+use crate::search::{in_check, legal_actions};
+
+// Piece layout shared with the chess eval
+const PAWN_PIECE: usize = 0;
+const KING_PIECE: usize = 5;
+
+pub fn piece_char(piece: usize, team: Team) -> char {
+    const CHESS_PIECES: [char; 6] = [ 'p', 'n', 'b', 'r', 'q', 'k' ];
+
+    let symbol = match CHESS_PIECES.get(piece) {
+        Some(&symbol) => symbol,
+        None => (b'a' + (piece % 26) as u8) as char
+    };
+
+    match team {
+        Team::White => symbol.to_ascii_uppercase(),
+        Team::Black => symbol
+    }
+}
+
+pub fn file_char(file: usize) -> char {
+    (b'a' + file as u8) as char
+}
+
+pub fn square_name<T: BitInt, const N: usize>(board: &Board<T, N>, sq: usize) -> String {
+    let cols = board.game.bounds.cols as usize;
+    format!("{}{}", file_char(sq % cols), sq / cols + 1)
+}
+
+// The promotion piece is whatever `display_uci_action` appends after the two squares
+fn promotion_suffix<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> Option<char> {
+    let squares = square_name(board, action.from as usize).len() + square_name(board, action.to as usize).len();
+    let uci = board.display_uci_action(action);
+
+    uci.get(squares..)
+        .and_then(|suffix| suffix.chars().next())
+        .map(|promotion| promotion.to_ascii_uppercase())
+}
+
+fn castle_notation<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> Option<&'static str> {
+    if action.piece as usize != KING_PIECE {
+        return None;
+    }
+
+    let cols = board.game.bounds.cols as usize;
+    let from_file = action.from as usize % cols;
+    let to_file = action.to as usize % cols;
+
+    // Castling is either a two-square king move or the king "capturing" its own rook
+    let onto_own_piece = BitBoard::index(action.to).and(board.state.team_to_move()).is_set();
+    if from_file.abs_diff(to_file) < 2 && !onto_own_piece {
+        return None;
+    }
+
+    Some(if to_file > from_file { "O-O" } else { "O-O-O" })
+}
+
+fn disambiguation<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> String {
+    let cols = board.game.bounds.cols as usize;
+    let from = action.from as usize;
+
+    let rivals: Vec<usize> = legal_actions(board)
+        .into_iter()
+        .filter(|other| other.piece == action.piece && other.to == action.to && other.from != action.from)
+        .map(|other| other.from as usize)
+        .collect();
+
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|&sq| sq % cols != from % cols) {
+        file_char(from % cols).to_string()
+    } else if rivals.iter().all(|&sq| sq / cols != from / cols) {
+        (from / cols + 1).to_string()
+    } else {
+        square_name(board, from)
+    }
+}
+
+pub fn display_san<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> String {
+    let cols = board.game.bounds.cols as usize;
+    let from = action.from as usize;
+    let to = action.to as usize;
+    let piece = action.piece as usize;
+
+    let mut san = match castle_notation(board, action) {
+        Some(castle) => castle.to_string(),
+        None => {
+            let is_capture = BitBoard::index(action.to).and(board.state.opposite_team()).is_set()
+                || (piece == PAWN_PIECE && from % cols != to % cols);
+
+            let mut san = String::new();
+            if piece == PAWN_PIECE {
+                if is_capture {
+                    san.push(file_char(from % cols));
+                }
+            } else {
+                san.push(piece_char(piece, Team::White));
+                san.push_str(&disambiguation(board, action));
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+
+            san.push_str(&square_name(board, to));
+
+            if let Some(promotion) = promotion_suffix(board, action) {
+                san.push('=');
+                san.push(promotion);
+            }
+
+            san
+        }
+    };
+
+    let state = board.play(action);
+    if in_check(board) {
+        san.push(if legal_actions(board).is_empty() { '#' } else { '+' });
+    }
+    board.restore(state);
+
+    san
+}
+
+// Displays a sequence of actions (ie. a PV), leaving the board as it was
+pub fn display_san_line<T: BitInt, const N: usize>(board: &mut Board<T, N>, actions: &[Action]) -> Vec<String> {
+    let mut states = vec![];
+    let mut line = vec![];
+
+    for &action in actions {
+        line.push(display_san(board, action));
+        states.push(board.play(action));
+    }
+
+    while let Some(state) = states.pop() {
+        board.restore(state);
+    }
+
+    line
+}
+
+// The legal move `display_uci_action` names this way; legal moves only, so a pseudo-legal
+// move sharing the name can't shadow it
+pub fn parse_uci<T: BitInt, const N: usize>(board: &mut Board<T, N>, input: &str) -> Option<Action> {
+    legal_actions(board).into_iter().find(|&action| board.display_uci_action(action) == input)
+}
+
+pub fn parse_san<T: BitInt, const N: usize>(board: &mut Board<T, N>, input: &str) -> Option<Action> {
+    let cols = board.game.bounds.cols as usize;
+    let san = input.trim().trim_end_matches(|c| matches!(c, '+' | '#' | '!' | '?'));
+    let actions = legal_actions(board);
+
+    if matches!(san, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+        let castle = if san.len() > 3 { "O-O-O" } else { "O-O" };
+        return actions
+            .into_iter()
+            .find(|&action| castle_notation(board, action) == Some(castle));
+    }
+
+    let mut chars: Vec<char> = san.chars()
+        .filter(|&c| !matches!(c, 'x' | ':' | '=' | '-'))
+        .collect();
+
+    // Anything trailing the destination square is a promotion
+    let promotion = match chars.last() {
+        Some(c) if c.is_ascii_alphabetic() => {
+            let promotion = c.to_ascii_uppercase();
+            chars.pop();
+            Some(promotion)
+        }
+        _ => None
+    };
+
+    let digits = chars.iter().rev().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || chars.len() < digits + 1 {
+        return None;
+    }
+
+    let dest_start = chars.len() - digits - 1;
+    if !chars[dest_start].is_ascii_lowercase() {
+        return None;
+    }
+
+    let to_file = (chars[dest_start] as u8 - b'a') as usize;
+    let to_rank: usize = chars[dest_start + 1..].iter().collect::<String>().parse().ok()?;
+    if to_rank == 0 {
+        return None;
+    }
+    let to = (to_rank - 1) * cols + to_file;
+
+    let rest = &chars[..dest_start];
+    let (piece, rest) = match rest.first() {
+        Some(&c) if c.is_ascii_uppercase() => (Some(c), &rest[1..]),
+        _ => (None, rest)
+    };
+
+    let from_file = rest.iter()
+        .find(|c| c.is_ascii_lowercase())
+        .map(|&c| (c as u8 - b'a') as usize);
+    let from_rank = rest.iter()
+        .filter(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse::<usize>()
+        .ok()
+        .map(|rank| rank.saturating_sub(1));
+
+    let candidates: Vec<Action> = actions
+        .into_iter()
+        .filter(|&action| {
+            let from = action.from as usize;
+            let action_piece = action.piece as usize;
+            let letter = if action_piece == PAWN_PIECE { None } else { Some(piece_char(action_piece, Team::White)) };
+
+            action.to as usize == to
+                && letter == piece
+                && from_file.map_or(true, |file| from % cols == file)
+                && from_rank.map_or(true, |rank| from / cols == rank)
+                && castle_notation(board, action).is_none()
+                && promotion_suffix(board, action) == promotion
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        &[action] => Some(action),
+        _ => None
+    }
+}
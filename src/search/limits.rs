@@ -0,0 +1,89 @@
+use chessing::{bitboard::BitInt, game::{action::Action, Board}};
+
+use super::{legal_actions, MAX_DEPTH};
+
+// Everything a `go` command can ask for, read in one place. Times are milliseconds; a value
+// that doesn't parse is left out as if it hadn't been sent
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u64>,
+    pub depth: Option<i32>,
+    pub nodes: Option<u64>,
+    // Moves, not plies
+    pub mate: Option<i32>,
+    pub movetime: Option<u64>,
+    pub infinite: bool,
+    // Read, but searched like any other `go` for now
+    pub ponder: bool,
+    // UCI moves; only the legal ones are kept when the search starts, see `search_moves`
+    pub searchmoves: Vec<String>
+}
+
+const KEYWORDS: [&str; 12] = [
+    "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite", "ponder", "searchmoves"
+];
+
+// `go [keyword value]... [searchmoves <move>...]`, in any order, from the whole line
+pub fn parse_limits(line: &str) -> Limits {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    let mut limits = Limits::default();
+    let mut index = 0;
+
+    while index < args.len() {
+        let value = args.get(index + 1).copied().unwrap_or("");
+
+        match args[index] {
+            "wtime" => limits.wtime = value.parse().ok(),
+            "btime" => limits.btime = value.parse().ok(),
+            "winc" => limits.winc = value.parse().ok(),
+            "binc" => limits.binc = value.parse().ok(),
+            "movestogo" => limits.movestogo = value.parse().ok(),
+            "depth" => limits.depth = value.parse().ok(),
+            "nodes" => limits.nodes = value.parse().ok(),
+            "mate" => limits.mate = value.parse().ok(),
+            "movetime" => limits.movetime = value.parse().ok(),
+            "infinite" => limits.infinite = true,
+            "ponder" => limits.ponder = true,
+            "searchmoves" => {
+                limits.searchmoves = args[index + 1..].iter()
+                    .take_while(|arg| !KEYWORDS.contains(arg))
+                    .map(|arg| arg.to_string())
+                    .collect();
+                index += limits.searchmoves.len();
+            }
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    limits
+}
+
+// A mate in n needs at most 2n - 1 plies to see
+pub fn max_depth(limits: &Limits) -> i32 {
+    match (limits.depth, limits.mate) {
+        (Some(depth), _) => depth,
+        (None, Some(mate)) => 2 * mate.max(1) - 1,
+        (None, None) => MAX_DEPTH
+    }
+}
+
+// Searched without a clock unless one is given as well: `go depth`, `go nodes` and `go mate`
+pub fn is_bounded(limits: &Limits) -> bool {
+    limits.depth.is_some() || limits.nodes.is_some() || limits.mate.is_some()
+}
+
+pub fn search_moves<T: BitInt, const N: usize>(board: &mut Board<T, N>, limits: &Limits) -> Vec<Action> {
+    if limits.searchmoves.is_empty() {
+        return vec![];
+    }
+
+    legal_actions(board).into_iter()
+        .filter(|&action| limits.searchmoves.contains(&board.display_uci_action(action)))
+        .collect()
+}
@@ -1,11 +1,24 @@
-use std::{cmp::Ordering, i32, vec};
+use std::{cmp::Ordering, i32, sync::{atomic::{self, AtomicBool, AtomicU64}, Arc}, thread, vec};
 
-use chessing::{bitboard::{BitBoard, BitInt}, game::{action::{Action, ActionRecord}, zobrist::ZobristTable, Board, GameState, Team}, uci::{respond::Info, Uci}};
-use ordering::{get_history, history_bonus, mvv_lva, sort_actions, sort_qs_actions, update_conthist, update_history, ContinuationHistory, History, ScoredAction, MAX_KILLERS};
+use chessing::{bitboard::{BitBoard, BitInt}, game::{action::{Action, ActionRecord}, zobrist::ZobristTable, Board, GameState, Team}};
+use params::{create_search_params, SearchParams};
+use see::{biggest_threat, hanging_squares, see, simple_gives_check};
+use stats::{create_search_stats, full_window_pays, record_iteration, record_nps_sample, record_probe, smoothed_nps, SearchStats};
+use limits::{max_depth, search_moves, Limits};
+use time::endgame_soft_time;
+use tt::{create_tt, hashfull, score_from_tt, score_to_tt, tt_entries, tt_probe, tt_store, Replacement, Tt, DEFAULT_HASH};
+use ordering::{clear_child_killers, countermove, decay_history, get_history, history_bonus, is_killer, is_quiet_check_score, mvv_lva, shift_killers, sort_actions, sort_qs_actions, update_conthist, update_countermove, update_history, ContinuationHistory, CounterMoves, History, ScoredAction, ScoredCapture, KILLER_PLIES, MAX_KILLERS};
 
-use crate::{eval::{eval, MATERIAL, ROOK}, util::current_time_millis};
+use crate::{eval::{evaluate, evaluate_within, is_dead_draw, params::{create_eval_params, EvalParams}, EvalTerm, MATERIAL}, input::{pending_multipv, stop_requested, take_pending_multipv}, nnue::NnueState, san::{display_san, display_san_line}, signals::shutdown_requested, util::{current_time_millis, Rng}, variants::VARIANTS};
 
+pub mod limits;
 mod ordering;
+pub mod params;
+pub mod quiet;
+mod see;
+pub mod stats;
+pub mod time;
+pub mod tt;
 
 #[derive(Clone, Debug, Copy)]
 pub enum Bounds {
@@ -20,44 +33,311 @@ pub struct TtEntry {
     pub best_move: Option<Action>,
     pub score: i32,
     pub depth: i32,
-    pub bounds: Bounds
+    pub bounds: Bounds,
+    // The node was on a PV at some point, see `tt_pv` in `search`
+    pub was_pv: bool,
+    // `tt_generation` when it was written
+    pub generation: u8
 }
 
+// One legal move at the root, as of the latest iteration that searched it
+#[derive(Clone, Debug)]
+pub struct RootMove {
+    pub action: Action,
+    // MIN when the move failed low, only the best move's score is exact
+    pub score: i32,
+    pub previous_score: i32,
+    // Nodes spent below this move over the whole search
+    pub nodes: u64,
+    pub pv: Vec<Action>
+}
+
+// The moves a node has searched, for the history maluses when a later one cuts off
+#[derive(Clone, Default)]
+pub struct SearchedMoves {
+    pub quiets: Vec<Action>,
+    pub quiet_checks: Vec<Action>,
+    pub noisies: Vec<Action>
+}
+
+#[derive(Clone)]
 pub struct SearchInfo {
     pub root_depth: i32,
+    // Best first after every completed iteration
+    pub root_moves: Vec<RootMove>,
+    // Position `root_moves` belong to, searching it again keeps their statistics
+    pub root_moves_hash: Option<u64>,
     pub best_move: Option<Action>,
     pub history: History,
     pub capture_history: History,
+    pub check_history: History,
     pub conthist: ContinuationHistory,
+    pub countermoves: CounterMoves,
+    pub counter_killers: CounterMoves,
     pub killers: Vec<Vec<Option<Action>>>,
-    pub pv_table: Vec<Vec<ActionRecord>>,
-    pub zobrist: ZobristTable,
-    pub quiet_lmr: Vec<Vec<i32>>,
-    pub noisy_lmr: Vec<Vec<i32>>,
+    // Game ply the killers were last recorded from
+    pub killer_root: usize,
+    // Per-ply PV stack, each node's line is its best move followed by the child's
+    pub pv_table: Vec<Vec<Action>>,
+    // PV of the last fully completed iteration
+    pub root_pv: Vec<Action>,
+    pub zobrist: Arc<ZobristTable>,
+    // [lmr_class][index][depth], in 1/256ths of a ply
+    pub lmr: Vec<Vec<Vec<i32>>>,
+    // One hash per position of the game, the current one last
     pub hashes: Vec<u64>,
     pub mobility: Vec<Option<(usize, Team)>>,
-    pub tt: Vec<Option<TtEntry>>,
-    pub tt_size: u64,
+    // The opponent's best reply to a null move at each ply, if the null search failed low
+    pub threats: Vec<Option<Action>>,
+    // Static eval at each ply, MIN when in check
+    pub evals: Vec<i32>,
+    // (hash, static eval) of the node last searched at each ply, so re-searching the same move
+    // (ie. after an LMR fail high) doesn't evaluate it again; cleared for each new move
+    pub eval_cache: Vec<Option<(u64, i32)>>,
+    // Halfmove clock at each ply, seeded from `root_halfmove` when a search starts
+    pub halfmove: Vec<usize>,
+    // Moves searched without a cutoff at each ply, kept between nodes so their buffers are reused
+    pub searched: Vec<SearchedMoves>,
+    pub root_halfmove: usize,
+    // Score of the last search that finished, for spotting swings between moves
+    pub last_search_score: Option<i32>,
+    // Searches finished since `ucinewgame`; the first ones follow book moves and get special treatment
+    pub game_searches: u32,
+    // What a draw is worth to the side that started the search, negated
+    pub contempt: i32,
+    pub root_team: Team,
+    // Set for searches on the clock with little material left, see `time::is_endgame`
+    pub endgame: bool,
+    // `go infinite`, where reported lines say when they end in a draw
+    pub analysis: bool,
+    // Up to this many centipawns of noise on root move scores while the game is younger than
+    // `root_noise_plies`, for variety without a book; 0 is off
+    pub root_noise: i32,
+    pub root_noise_plies: usize,
+    pub root_noise_seed: u64,
+    pub params: SearchParams,
+    pub eval_params: EvalParams,
+    // Shared with the helper threads
+    pub tt: Arc<Tt>,
+    pub tt_replacement: Replacement,
+    // Bumped every search, so `Replacement::Aged` can tell old entries apart
+    pub tt_generation: u8,
     pub nodes: u64,
     pub score: i32,
     pub abort: bool,
-    pub time_to_abort: u128
+    pub time_to_abort: u128,
+    pub search_start: u128,
+    pub max_nodes: u64,
+    pub max_depth: i32,
+    pub stop: StopReason,
+    pub stats: SearchStats,
+    // Node count at which the clock is next read
+    pub next_time_check: u64,
+    pub seldepth: usize,
+    pub generic: bool,
+    // See `Variant`, these gate null move pruning and spot dead draws
+    pub royal_pieces: Vec<usize>,
+    pub filler_pieces: Vec<usize>,
+    pub minor_pieces: Vec<usize>,
+    pub san_output: bool,
+    // Human-readable commentary lines alongside the usual UCI output, see `kibitz`
+    pub kibitz: bool,
+    // Lines searched and reported at the root, and root moves left out while finding the later ones
+    pub multipv: usize,
+    pub excluded_root: Vec<Action>,
+    // `go searchmoves`, the only root moves searched; empty is all of them
+    pub search_moves: Vec<Action>,
+    pub show_wdl: bool,
+    pub last_kibitz: u128,
+    pub nnue: Option<NnueState>,
+    // Search threads including this one, the others being Lazy SMP helpers
+    pub threads: usize,
+    // Set once the main thread is done, so its helpers stop with it
+    pub shared_stop: Arc<AtomicBool>,
+    // Nodes the helpers have searched, added as they go so the main thread can report them
+    pub shared_nodes: Arc<AtomicU64>,
+    pub helper: bool,
+    // A helper's nodes already added to `shared_nodes`
+    pub reported_nodes: u64,
+    // Only the main thread of a UCI search says which root move it's on
+    pub report_currmove: bool,
+    // Classical eval terms switched off by the `Ablate...` options
+    pub ablated: Vec<EvalTerm>
 }
 
 pub const MAX: i32 = 1_000_000;
 pub const MIN: i32 = -1_000_000;
+fn lmr_class(noisy: bool, pv: bool, improving: bool) -> usize {
+    (noisy as usize) * 4 + (pv as usize) * 2 + improving as usize
+}
+
+// Plies to reduce a late move by, before the per-move exceptions in the move loop
+// `class` picks the table, see `lmr_class`
+fn reduction(info: &SearchInfo, class: usize, index: usize, depth: i32, history: i32, piece: usize) -> i32 {
+    let table = &info.lmr[class];
+    let r = table[index.min(table.len() - 1)][(depth as usize).min(table[0].len() - 1)]
+        + info.params.lmr_piece.get(piece).copied().unwrap_or(0);
+
+    ((r - history.clamp(-512, 512)) / 256).max(0)
+}
+
+pub const QS_FUTILITY_MARGIN: i32 = 200;
+pub const NOISY_FUTILITY_DEPTH: i32 = 4;
+pub const NOISY_FUTILITY_MARGIN: i32 = 120;
+// Singular extensions look at nodes this deep whose TT entry is at most this much shallower,
+// searching the other moves against the TT score less this much per ply
+pub const SINGULAR_MIN_DEPTH: i32 = 8;
+pub const SINGULAR_TT_DEPTH: i32 = 3;
+pub const SINGULAR_MARGIN: i32 = 2;
+// Near the leaves a move is skipped if it loses more than this on its square, per ply (quiets)
+// or per ply squared (captures)
+pub const SEE_PRUNE_DEPTH: i32 = 6;
+pub const SEE_QUIET_MARGIN: i32 = 60;
+pub const SEE_NOISY_MARGIN: i32 = 20;
+// Killers and the countermove are never reduced to less than this
+pub const TIER_MIN_DEPTH: i32 = 3;
+// Quiets with at least this much history are never skipped by late move pruning
+pub const LMP_HISTORY_KEEP: i32 = 200;
+// Anything closer to MIN/MAX than this is a forced mate
+pub const MATE_THRESHOLD: i32 = MAX - 1000;
+pub const ROOT_NOISE_PLIES: usize = 8;
+
+// Mates are counted in moves, negative when being mated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    Cp(i32),
+    Mate(i32)
+}
+
+// Why iterative deepening stopped, for time management diagnostics
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    // Ran out of iterations
+    Depth,
+    // Another iteration wasn't started, the soft limit had passed
+    SoftTime,
+    // An iteration was cut off by the hard limit
+    HardTime,
+    Nodes,
+    Signal,
+    // `stop` or `quit` arrived mid-search
+    Stop
+}
 
-fn set_or_push<T>(vec: &mut Vec<T>, index: usize, item: T) {
-    if vec.len() > index {
-        vec[index] = item;
-    } else if vec.len() == index {
-        vec.push(item);
+pub fn stop_reason_name(reason: StopReason) -> &'static str {
+    match reason {
+        StopReason::Depth => "depth",
+        StopReason::SoftTime => "soft",
+        StopReason::HardTime => "hard",
+        StopReason::Nodes => "nodes",
+        StopReason::Signal => "signal",
+        StopReason::Stop => "stop"
     }
 }
 
+fn abort_reason(info: &SearchInfo) -> StopReason {
+    if shutdown_requested() {
+        StopReason::Signal
+    } else if stop_requested() {
+        StopReason::Stop
+    } else if info.nodes >= info.max_nodes {
+        StopReason::Nodes
+    } else {
+        StopReason::HardTime
+    }
+}
+
+// What a finished search hands back, so callers don't need to read it out of `SearchInfo`
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best: Action,
+    pub ponder: Option<Action>,
+    pub score: Score,
+    // Always exact for now, as aborted iterations are thrown away
+    pub bound: Bounds,
+    pub depth: i32,
+    pub seldepth: usize,
+    pub nodes: u64,
+    pub pv: Vec<Action>,
+    pub root_moves: Vec<RootMove>,
+    pub stop: StopReason,
+    pub time: u64
+}
+
+pub fn to_score(score: i32) -> Score {
+    if score >= MATE_THRESHOLD {
+        Score::Mate((MAX - score + 1) / 2)
+    } else if score <= -MATE_THRESHOLD {
+        Score::Mate(-(score - MIN + 1) / 2)
+    } else {
+        Score::Cp(score)
+    }
+}
+
+// Mate scores map back onto the internal scale, for anything that wants a plain number
+pub fn score_value(score: Score) -> i32 {
+    match score {
+        Score::Cp(score) => score,
+        Score::Mate(moves) if moves > 0 => MAX - (2 * moves - 1),
+        Score::Mate(moves) => MIN - 2 * moves
+    }
+}
+
+// A node's PV starts empty, so a child that cuts off early never leaves a stale line behind
+fn clear_pv(info: &mut SearchInfo, ply: usize) {
+    if let Some(pv) = info.pv_table.get_mut(ply) {
+        pv.clear();
+    }
+}
+
+fn update_pv(info: &mut SearchInfo, ply: usize, action: Action) {
+    if ply >= info.pv_table.len() {
+        return;
+    }
+
+    let mut line = vec![ action ];
+    if let Some(child) = info.pv_table.get(ply + 1) {
+        line.extend_from_slice(child);
+    }
+
+    info.pv_table[ply] = line;
+}
+
+// Replays a line with make/unmake, checking every move is legal where it's played
+pub fn pv_is_legal<T: BitInt, const N: usize>(board: &mut Board<T, N>, pv: &[Action]) -> bool {
+    let mut states = vec![];
+    let mut legal = true;
+
+    for &action in pv {
+        if !legal_actions(board).contains(&action) {
+            legal = false;
+            break;
+        }
+
+        states.push(board.play(action));
+    }
+
+    while let Some(state) = states.pop() {
+        board.restore(state);
+    }
+
+    legal
+}
+
 // Generalize "noisiness"
 // Checks if the amount of pieces of a given team/type are changed
 fn is_noisy_general<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> bool {
+    // Settled from the target square where possible, as playing every move is most of qsearch's
+    // cost: landing on an enemy piece is a capture, and a plain move (no `info`) onto an empty
+    // square can't change what's on the board
+    if BitBoard::index(action.to).and(board.state.opposite_team()).is_set() {
+        return true;
+    }
+
+    if action.info == 0 && !BitBoard::index(action.to).and(board.state.team_to_move()).is_set() {
+        return false;
+    }
+
     let white = board.state.white.count();
     let black = board.state.black.count();
     let pieces = board.state.pieces.map(|piece| piece.count());
@@ -75,6 +355,22 @@ fn is_noisy_general<T: BitInt, const N: usize>(board: &mut Board<T, N>, action:
     new_pieces != pieces
 }
 
+// Chess layout: pawns are piece 0, and a promotion's `info` is the new piece plus 2
+const PAWN_PIECE: usize = 0;
+const QUEEN_PIECE: usize = 4;
+
+pub fn promotion_piece(action: Action) -> Option<usize> {
+    if action.piece as usize == PAWN_PIECE && action.info >= 3 {
+        Some((action.info - 2) as usize)
+    } else {
+        None
+    }
+}
+
+pub fn is_underpromotion(action: Action) -> bool {
+    promotion_piece(action).is_some_and(|piece| piece != QUEEN_PIECE)
+}
+
 // Chess-specific "noisiness"
 fn is_noisy_chess<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> bool {
     if action.piece == 0 && action.info >= 1 {
@@ -85,11 +381,119 @@ fn is_noisy_chess<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Ac
     return BitBoard::index(action.to).and(board.state.opposite_team()).is_set();
 }
 
-fn is_noisy<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> bool {
+pub fn is_noisy<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, action: Action) -> bool {
     // For chess, `is_noisy_chess` is idential to `is_noisy_general`
     // However, for some variants this may not be the case
-    // is_noisy_general(board, action)
-    is_noisy_chess(board, action)
+    if info.generic {
+        is_noisy_general(board, action)
+    } else {
+        is_noisy_chess(board, action)
+    }
+}
+
+pub fn legal_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>) -> Vec<Action> {
+    board.list_actions()
+        .into_iter()
+        .filter(|&action| {
+            let history = board.play(action);
+            let is_legal = board.game.rules.is_legal(board);
+            board.restore(history);
+            is_legal
+        })
+        .collect()
+}
+
+// The side to move is in check if passing would leave its king capturable
+pub fn in_check<T: BitInt, const N: usize>(board: &mut Board<T, N>) -> bool {
+    let state = board.play_null();
+    let in_check = !board.game.rules.is_legal(board);
+    board.restore(state);
+
+    in_check
+}
+
+// Promotions, castling and en passant are played out, anything else is read off the attack map
+pub fn gives_check<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, action: Action) -> bool {
+    if promotion_piece(action).is_none() {
+        if let Some(checks) = simple_gives_check(board, info, action) {
+            return checks;
+        }
+    }
+
+    let state = board.play(action);
+    let gives_check = in_check(board);
+    board.restore(state);
+
+    gives_check
+}
+
+// Iterative deepening never goes past this, the per-ply stacks are sized for it
+pub const MAX_DEPTH: i32 = 99;
+// Quiescence and extensions can run past `MAX_DEPTH`, so a node this deep just returns its static eval
+pub const MAX_PLY: usize = 128;
+// Bytes of stack for each search thread (the main one included), as `search` and `quiescence`
+// recurse once per ply
+pub const SEARCH_STACK: usize = 64 * 1024 * 1024;
+
+// For quiescence and eval calls outside of a search, clears whatever the last search left behind
+pub fn prepare_probe(info: &mut SearchInfo) {
+    info.abort = false;
+    info.time_to_abort = u128::MAX;
+    info.max_nodes = u64::MAX;
+    info.halfmove[0] = info.root_halfmove;
+}
+
+fn report_helper_nodes(info: &mut SearchInfo) {
+    if info.helper {
+        info.shared_nodes.fetch_add(info.nodes - info.reported_nodes, atomic::Ordering::Relaxed);
+        info.reported_nodes = info.nodes;
+    }
+}
+
+// Every thread's nodes, on the main thread
+pub fn total_nodes(info: &SearchInfo) -> u64 {
+    info.nodes + info.shared_nodes.load(atomic::Ordering::Relaxed)
+}
+
+// Reading the clock is slow, so it's only done every so many nodes, wherever in the tree they are
+pub const TIME_CHECK_NODES: u64 = 1024;
+
+fn should_abort(info: &mut SearchInfo) -> bool {
+    if info.abort {
+        return true;
+    }
+
+    if info.nodes >= info.max_nodes {
+        info.abort = true;
+    } else if info.nodes >= info.next_time_check {
+        info.next_time_check = info.nodes + TIME_CHECK_NODES;
+        report_helper_nodes(info);
+        info.abort = current_time_millis() >= info.time_to_abort
+            || info.shared_stop.load(atomic::Ordering::Relaxed)
+            || shutdown_requested()
+            || stop_requested()
+            // Only the main thread restarts for a new MultiPV, the helpers carry on until it's done
+            || (!info.helper && pending_multipv().is_some());
+    }
+
+    info.abort
+}
+
+// A capture or a filler piece (pawn) move resets the halfmove clock
+pub fn resets_halfmove<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, action: Action) -> bool {
+    info.filler_pieces.contains(&(action.piece as usize))
+        || BitBoard::index(action.to).and(board.state.opposite_team()).is_set()
+}
+
+// `None` is a null move
+fn record_halfmove<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &mut SearchInfo, ply: usize, action: Option<Action>) {
+    if ply + 1 >= info.halfmove.len() {
+        return;
+    }
+
+    let resets = action.is_some_and(|action| resets_halfmove(board, info, action));
+
+    info.halfmove[ply + 1] = if resets { 0 } else { info.halfmove[ply] + 1 };
 }
 
 pub fn quiescence<T: BitInt, const N: usize>(
@@ -99,7 +503,16 @@ pub fn quiescence<T: BitInt, const N: usize>(
     mut alpha: i32, 
     beta: i32, 
 ) -> i32 {
-    let stand_pat = eval(board, info, ply);
+    if should_abort(info) { return 0; }
+
+    info.seldepth = info.seldepth.max(ply);
+    clear_pv(info, ply);
+
+    if ply + 1 >= MAX_PLY {
+        return evaluate(board, info, ply);
+    }
+
+    let stand_pat = evaluate_within(board, info, ply, alpha, beta);
     let mut best = stand_pat;
 
     if stand_pat >= beta {
@@ -116,14 +529,42 @@ pub fn quiescence<T: BitInt, const N: usize>(
     let mut captures = Vec::with_capacity(actions.len());
 
     for act in actions {
-        if is_noisy(board, act) {
-            captures.push(act);
+        if !is_noisy(board, info, act) {
+            continue;
+        }
+
+        // Underpromotions are only worth resolving when they do something a queen wouldn't
+        if is_underpromotion(act)
+            && !BitBoard::index(act.to).and(board.state.opposite_team()).is_set()
+            && !gives_check(board, info, act) {
+            continue;
         }
+
+        captures.push(act);
     }
     
     let scored_captures = sort_qs_actions(board, info, captures);
 
-    for ScoredAction(act, _) in scored_captures {
+    for ScoredCapture(act, _, see_value) in scored_captures {
+        // A capture that loses material on the exchange doesn't resolve anything stand pat hasn't
+        if see_value < 0 && promotion_piece(act).is_none() {
+            continue;
+        }
+
+        // Futility per capture: even taking the victim for free leaves us short of alpha
+        if promotion_piece(act).is_none() && alpha.abs() < MATE_THRESHOLD {
+            let victim = if BitBoard::index(act.to).and(board.state.opposite_team()).is_set() {
+                board.piece_at(act.to).and_then(|piece| MATERIAL.get(piece as usize).copied()).unwrap_or(0)
+            } else {
+                0
+            };
+
+            if stand_pat + victim + QS_FUTILITY_MARGIN <= alpha {
+                continue;
+            }
+        }
+
+        record_halfmove(board, info, ply, Some(act));
         let state = board.play(act);
         let is_legal = board.game.rules.is_legal(board);
 
@@ -137,10 +578,14 @@ pub fn quiescence<T: BitInt, const N: usize>(
         let score = -quiescence(board, info, ply + 1, -beta, -alpha);
         board.restore(state);
 
+        // Nothing past an abort is searched, so a node limit is never overshot
+        if info.abort { return 0; }
+
         if score > best {
             best = score;
             if score > alpha {
                 alpha = score;
+                update_pv(info, ply, act);
             }
         }
 
@@ -149,20 +594,78 @@ pub fn quiescence<T: BitInt, const N: usize>(
         }
     }
 
+    if info.abort { return 0; }
+
     best
 }
 
+// The same for a given seed, position and move, so a seeded game can be replayed exactly
+fn root_noise<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, action: Action) -> i32 {
+    let hash = board.game.rules.hash(board, &info.zobrist);
+    let action_key = ((action.from as u64) << 32) | ((action.to as u64) << 16) | action.info as u64;
+    let mut rng = Rng::new(info.root_noise_seed ^ hash ^ action_key.wrapping_mul(0x9E3779B97F4A7C15));
+
+    rng.below(2 * info.root_noise as usize + 1) as i32 - info.root_noise
+}
+
+// Pieces on the board besides royal and filler ones (kings and pawns), both sides together
+pub fn phase_pieces<T: BitInt, const N: usize>(board: &Board<T, N>, info: &SearchInfo) -> u32 {
+    board.state.pieces.iter()
+        .enumerate()
+        .filter(|(piece, _)| !info.royal_pieces.contains(piece) && !info.filler_pieces.contains(piece))
+        .map(|(_, pieces)| pieces.count() as u32)
+        .sum()
+}
+
+// From this many pieces (see `phase_pieces`) contempt applies in full, below it fades out linearly:
+// a middlegame draw gives up winning chances, a bare ending has few left to give up
+pub const FULL_CONTEMPT_PIECES: u32 = 8;
+
+// Contempt makes draws unattractive for the root side and attractive for its opponent
+fn draw_score<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo) -> i32 {
+    let pieces = phase_pieces(board, info).min(FULL_CONTEMPT_PIECES);
+    let contempt = info.contempt * pieces as i32 / FULL_CONTEMPT_PIECES as i32;
+
+    if board.state.moving_team == info.root_team {
+        -contempt
+    } else {
+        contempt
+    }
+}
+
+// The side to move has something besides royal and filler pieces
 fn zugzwang_unlikely<T: BitInt, const N: usize>(
-    board: &mut Board<T, N>
+    board: &mut Board<T, N>,
+    info: &SearchInfo
 ) -> bool {
-    let king = board.state.pieces[5];
-    let pawns = board.state.pieces[0];
     let team = board.state.team_to_move();
 
-    team != team.and(king.or(pawns))
-    
+    (0..board.state.pieces.len())
+        .filter(|piece| !info.royal_pieces.contains(piece) && !info.filler_pieces.contains(piece))
+        .any(|piece| board.state.pieces[piece].and(team).is_set())
+}
+
+// Conthist needs a real move to continue from, a null move breaks the chain
+fn continuation_action(record: Option<&ActionRecord>) -> Option<Action> {
+    match record {
+        Some(&ActionRecord::Action(action)) => Some(action),
+        Some(ActionRecord::Null()) => None,
+        None => None
+    }
 }
 
+fn cached_see<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, action: Action, exchange: &mut Option<i32>) -> i32 {
+    *exchange.get_or_insert_with(|| see(board, info, action))
+}
+
+// Found by an earlier MultiPV line, or not among `go searchmoves`
+fn left_out_of_root(info: &SearchInfo, action: Action) -> bool {
+    info.excluded_root.contains(&action) || (!info.search_moves.is_empty() && !info.search_moves.contains(&action))
+}
+
+// `excluded` is a move to leave out, for the singular search: the node is then the same position
+// with one move fewer, so its result never goes to or comes from the TT
+#[allow(clippy::too_many_arguments)]
 pub fn search<T: BitInt, const N: usize>(
     board: &mut Board<T, N>, 
     info: &mut SearchInfo,
@@ -170,54 +673,66 @@ pub fn search<T: BitInt, const N: usize>(
     ply: usize,
     mut alpha: i32, 
     beta: i32, 
-    is_pv: bool
+    is_pv: bool,
+    excluded: Option<Action>
 ) -> i32 {
-    if depth >= 4 && !info.abort {
-        info.abort = current_time_millis() >= info.time_to_abort;
-    }
+    if should_abort(info) { return 0; }
+    clear_pv(info, ply);
 
-    if info.abort { return 0; }
-    //info.pv_table[ply] = vec![];
+    info.seldepth = info.seldepth.max(ply);
 
-    if depth <= 0 {
+    if depth <= 0 || ply + 1 >= MAX_PLY {
         return quiescence(board, info, ply, alpha, beta);
     }
 
-    let eval = eval(board, info, ply);
-    if !is_pv && depth <= 3 {
-        if eval - (100 * depth) >= beta {
+    let hash = board.game.rules.hash(board, &info.zobrist);
+
+    let eval = match info.eval_cache[ply] {
+        Some((cached_hash, eval)) if cached_hash == hash => eval,
+        _ => evaluate(board, info, ply)
+    };
+    info.eval_cache[ply] = Some((hash, eval));
+    let is_in_check = in_check(board);
+
+    info.evals[ply] = if is_in_check { MIN } else { eval };
+    let improving = !is_in_check && ply >= 2 && info.evals[ply - 2] != MIN && eval > info.evals[ply - 2];
+
+    let tt_entry = tt_probe(info, hash);
+
+    // Nodes that were ever on a PV are likely to be again, so they're pruned and reduced less
+    let tt_pv = is_pv || tt_entry.as_ref().is_some_and(|entry| entry.was_pv);
+
+    // Reverse futility pruning, never against a mate score, while in check or in a TT-PV node
+    if !tt_pv && !is_in_check && excluded.is_none() && depth <= info.params.rfp_max_depth && beta.abs() < MATE_THRESHOLD {
+        if eval - info.params.rfp_margins[improving as usize][depth as usize] >= beta {
             return eval;
         }
     }
 
-    let hash = board.game.rules.hash(board, &info.zobrist);
-
     if info.hashes.contains(&hash) && ply > 0 {
-        return 0;
+        return draw_score(board, info);
     }
 
-    let index = (hash % info.tt_size) as usize;
+    // Long before the game itself calls it, neither side can mate
+    if ply > 0 && is_dead_draw(board, info) {
+        return draw_score(board, info);
+    }
 
     let mut found_best_move: Option<Action> = None;
 
-    let tt_hit = &info.tt[index];
-    match tt_hit {
-        Some(entry) => {
-            if hash == entry.hash {
-                let is_in_bounds = match entry.bounds {
-                    Bounds::Exact => true,
-                    Bounds::Lower => entry.score >= beta,
-                    Bounds::Upper => entry.score < alpha
-                };
-    
-                if entry.depth >= depth && is_in_bounds && !is_pv {
-                    return entry.score;
-                }
-    
-                found_best_move = entry.best_move;
-            }
+    if let Some(entry) = &tt_entry {
+        let score = score_from_tt(entry.score, ply);
+        let is_in_bounds = match entry.bounds {
+            Bounds::Exact => true,
+            Bounds::Lower => score >= beta,
+            Bounds::Upper => score < alpha
+        };
+
+        if entry.depth >= depth && is_in_bounds && !is_pv && excluded.is_none() {
+            return score;
         }
-        None => {}
+
+        found_best_move = entry.best_move;
     }
 
     let actions = board.list_actions();
@@ -241,37 +756,39 @@ pub fn search<T: BitInt, const N: usize>(
             return MIN + ply as i32;
         }
         GameState::Draw => {
-            return 0;
+            return draw_score(board, info);
         }
         GameState::Ongoing => {
             // continue evaluation
         }
     }
 
-    let two_ply = match board.history.get(board.history.len().wrapping_sub(2)) {
-        Some(&ActionRecord::Action(action)) => Some(action),
-        _ => None
-    };
-
-    let previous = match board.history.last() {
-        Some(&ActionRecord::Action(action)) => Some(action),
-        _ => None
-    };
+    let two_ply = continuation_action(board.history.get(board.history.len().wrapping_sub(2)));
+    let previous = continuation_action(board.history.last());
 
     let null_last_move = matches!(board.history.last(), Some(ActionRecord::Null()));
+    info.threats[ply] = None;
     
     let state = board.play_null();
     board.restore(state);
 
-    if !is_pv && depth >= 3 && zugzwang_unlikely(board) && !null_last_move {
+    if !is_pv && depth >= 3 && excluded.is_none() && zugzwang_unlikely(board, info) && !null_last_move {
         let reduction = 3 + (depth / 5);
         let nm_depth = depth - reduction;
 
+        record_halfmove(board, info, ply, None);
         let state = board.play_null();
         let is_legal = board.game.rules.is_legal(board);
 
         if is_legal {
-            let null_score = -search(board, info, nm_depth, ply, -beta, -beta + 1, is_pv);
+            // The null search is a real child node, so it gets its own ply for killers and mobility
+            let null_score = -search(board, info, nm_depth, ply + 1, -beta, -beta + 1, is_pv, None);
+
+            // Whatever refuted passing is what this node has to deal with
+            if null_score < beta {
+                info.threats[ply] = tt_move(board, info);
+            }
+
             board.restore(state);
     
             if null_score >= beta {
@@ -286,7 +803,47 @@ pub fn search<T: BitInt, const N: usize>(
         }
     }
     
-    info.hashes.push(hash);
+    // Singular extensions: if every other move fails low against a margin below the TT score,
+    // the TT move is the only one holding the position up and gets searched a ply deeper.
+    // Searched before this node's hash goes on the stack, or it would be taken for a repetition
+    let mut extension = 0;
+
+    if let (Some(entry), Some(tt_action)) = (&tt_entry, found_best_move) {
+        let tt_score = score_from_tt(entry.score, ply);
+
+        if ply > 0 && excluded.is_none()
+            && depth >= SINGULAR_MIN_DEPTH
+            && entry.depth >= depth - SINGULAR_TT_DEPTH
+            && !matches!(entry.bounds, Bounds::Upper)
+            && tt_score.abs() < MATE_THRESHOLD
+            && ply < 2 * info.root_depth.max(1) as usize {
+            let singular_beta = tt_score - SINGULAR_MARGIN * depth;
+            let threat = info.threats[ply];
+
+            let score = search(board, info, (depth - 1) / 2, ply, singular_beta - 1, singular_beta, false, Some(tt_action));
+            info.threats[ply] = threat;
+
+            if info.abort { return 0; }
+
+            if score < singular_beta {
+                extension = 1;
+            } else if singular_beta >= beta {
+                // Multicut: some other move beats beta too, so this node would cut either way
+                return singular_beta;
+            } else if tt_score >= beta {
+                // Not singular, and the TT move is expected to cut anyway; the others can take over if it doesn't
+                extension = -1;
+            }
+        }
+    }
+
+    let root_node = ply == 0;
+
+    // The root is already the last of the game's hashes
+    if !root_node {
+        info.hashes.push(hash);
+    }
+    clear_child_killers(info, ply);
 
     let scored_actions = sort_actions(board, info, ply, legal_actions, previous, two_ply, found_best_move);
 
@@ -294,67 +851,162 @@ pub fn search<T: BitInt, const N: usize>(
     let mut best_move: Option<Action> = None;
 
     let mut bounds = Bounds::Upper; // ALL-node: no move exceeded alpha
-    let root_node = depth == info.root_depth;
 
-    let mut quiets: Vec<Action> = vec![];
-    let mut noisies: Vec<Action> = vec![];
+    let mut searched = std::mem::take(&mut info.searched[ply]);
+    searched.quiets.clear();
+    searched.quiet_checks.clear();
+    searched.noisies.clear();
+    let mut hanging: Option<Vec<usize>> = None;
+
+    for (index, &ScoredAction(act, order_score)) in scored_actions.iter().enumerate() {
+        if (root_node && left_out_of_root(info, act)) || excluded == Some(act) {
+            continue;
+        }
+
+        // Only the searches of this one move may share the child's eval
+        if let Some(child) = info.eval_cache.get_mut(ply + 1) {
+            *child = None;
+        }
 
-    for (index, &ScoredAction(act, _)) in scored_actions.iter().enumerate() {
-        let is_noisy = is_noisy(board, act);
+        let is_noisy = is_noisy(board, info, act);
         let is_quiet = !is_noisy;
+        let is_quiet_check = is_quiet && is_quiet_check_score(order_score);
         let team = board.state.moving_team;
+        // Only worked out once a pruning or reduction rule gets as far as needing it
+        let mut exchange: Option<i32> = None;
 
         if index > 3 + 2 * (depth * depth) as usize && is_quiet {
-            continue;
+            // Checks, well-liked quiets and moves saving a hanging piece are never skipped
+            let history = get_history(board, info, act, previous, two_ply, false);
+            let hanging = hanging.get_or_insert_with(|| hanging_squares(board, info));
+            let escapes = hanging.contains(&(act.from as usize)) && see(board, info, act) >= 0;
+
+            if !is_quiet_check && history < LMP_HISTORY_KEEP && !escapes {
+                continue;
+            }
         }
 
         let r = if index >= 2 {
-            let mut r = if is_noisy {
-                info.noisy_lmr[index][depth as usize]
-            } else {
-                info.quiet_lmr[index][depth as usize]
-            };
-
             let history = get_history(board, info, act, previous, two_ply, is_noisy);
-            r -= history.clamp(-512, 512);
+            let class = lmr_class(is_noisy, is_pv, improving);
+            let mut r = reduction(info, class, index, depth, history, act.piece as usize);
+
+            // Moving the threatened piece away is searched at full depth
+            if info.threats[ply].is_some_and(|threat| threat.to == act.from) {
+                r = 0;
+            }
 
-            r /= 256;
+            if tt_pv && !is_pv {
+                r -= 1;
+            }
+
+            // Captures losing material on the exchange go one ply shallower
+            if is_noisy && cached_see(board, info, act, &mut exchange) < 0 {
+                r += 1;
+            }
+
+            // Ordering tiers: the TT move is never reduced, killers and the countermove only down to a floor
+            if found_best_move == Some(act) {
+                r = 0;
+            } else if is_killer(info, ply, act) || countermove(info, team, previous) == Some(act) {
+                r = r.min((depth - 1 - TIER_MIN_DEPTH).max(0));
+            }
+
+            // A checking move always keeps at least one ply
+            // Ordering already found out for quiets
+            if r > 0 && (is_quiet_check || (is_noisy && gives_check(board, info, act))) {
+                r = r.min((depth - 2).max(0));
+            }
 
-            (r as i32).max(0)
+            r.max(0)
         } else {
             0
         };
         let lmr = r > 0;
         
-        if !root_node && is_quiet && (depth - r) <= 8 && eval + 300 + (75 * depth) <= alpha {
+        let piece_margin = info.params.futility_piece.get(act.piece as usize).copied().unwrap_or(0);
+        if !root_node && is_quiet && (depth - r) <= 8 && eval + 300 + (75 * depth) + piece_margin <= alpha {
             continue;
         }
 
+        // Capture futility: even winning what SEE says it wins, the capture can't get near alpha
+        if !root_node && !is_pv && !is_in_check && is_noisy && index > 0
+            && depth <= NOISY_FUTILITY_DEPTH
+            && alpha.abs() < MATE_THRESHOLD
+            && promotion_piece(act).is_none()
+            && eval + cached_see(board, info, act, &mut exchange) + NOISY_FUTILITY_MARGIN * depth <= alpha {
+            continue;
+        }
+
+        // SEE pruning, once a move has kept us out of a mating line
+        if !root_node && !is_in_check && depth <= SEE_PRUNE_DEPTH
+            && best > -MATE_THRESHOLD
+            && !is_quiet_check
+            && promotion_piece(act).is_none() {
+            let threshold = if is_quiet { -SEE_QUIET_MARGIN * depth } else { -SEE_NOISY_MARGIN * depth * depth };
+
+            if cached_see(board, info, act, &mut exchange) < threshold {
+                continue;
+            }
+        }
+
+        if root_node && info.report_currmove {
+            report_currmove(board, info, depth, act, index);
+        }
+
+        // Root noise shifts the window the move is searched with rather than its result, so a
+        // fail low stays a fail low once the noise is added
+        let noise = if root_node && info.root_noise > 0 && board.history.len() < info.root_noise_plies {
+            root_noise(board, info, act)
+        } else {
+            0
+        };
+        let (search_alpha, search_beta) = (alpha - noise, beta - noise);
+
+        record_halfmove(board, info, ply, Some(act));
         let history = board.play(act);
 
         info.nodes += 1;
+        let nodes_before = info.nodes;
 
-        let new_depth = depth - 1;
+        let new_depth = depth - 1 + if found_best_move == Some(act) { extension } else { 0 };
         let mut score: i32 = MIN; 
         
+        // Low in the tree a PV node's null-window probe can fail high so often that going
+        // straight to the full window is cheaper
+        let full_window = is_pv && index > 0 && !lmr && full_window_pays(&info.stats, depth);
+        let probed = is_pv && index > 0 && !full_window;
+
         if lmr {
             let reduced = new_depth - r;
 
-            score = -search(board, info, reduced, ply + 1, -alpha - 1, -alpha, false);
+            score = -search(board, info, reduced, ply + 1, -search_alpha - 1, -search_alpha, false, None);
             
-            if score > alpha && reduced < new_depth {
-                score = -search(board, info, new_depth, ply + 1, -alpha - 1, -alpha, false);
+            if score > search_alpha && reduced < new_depth {
+                score = -search(board, info, new_depth, ply + 1, -search_alpha - 1, -search_alpha, false, None);
             }
-        } else if !is_pv || index > 0 {
-            score = -search(board, info, new_depth, ply + 1, -alpha - 1, -alpha, false);
+        } else if (!is_pv || index > 0) && !full_window {
+            score = -search(board, info, new_depth, ply + 1, -search_alpha - 1, -search_alpha, false, None);
+        }
+
+        if probed {
+            record_probe(&mut info.stats, depth, score > search_alpha);
         }
         
-        if is_pv && (index == 0 || score > alpha) {
-            score = -search(board, info, new_depth, ply + 1, -beta, -alpha, is_pv);
+        if is_pv && (index == 0 || full_window || score > search_alpha) {
+            score = -search(board, info, new_depth, ply + 1, -search_beta, -search_alpha, is_pv, None);
         }
 
         board.restore(history);
 
+        if score.abs() < MATE_THRESHOLD {
+            score += noise;
+        }
+
+        if root_node && !info.abort {
+            update_root_move(info, ply, act, score, index == 0 || score > alpha, info.nodes - nodes_before);
+        }
+
         if score > best {
             best = score;
             best_move = Some(act);
@@ -363,26 +1015,7 @@ pub fn search<T: BitInt, const N: usize>(
                 alpha = score;
 
                 if is_pv {
-                    let ply = ply as usize;
-
-                    match info.pv_table.get((ply + 1) as usize) {
-                        Some(pv_moves) => {
-                            for (i, pv) in pv_moves.clone().iter().enumerate() {
-                                match pv {
-                                    ActionRecord::Null() => {
-                                        set_or_push(&mut info.pv_table[ply], i + 1, ActionRecord::Null());
-                                        break;
-                                    }
-                                    &ActionRecord::Action(act) => {
-                                        set_or_push(&mut info.pv_table[ply], i + 1, ActionRecord::Action(act));
-                                    }
-                                }
-                            }
-                        }
-                        None => {}
-                    }
-
-                    set_or_push(&mut info.pv_table[ply], 0, ActionRecord::Action(act));
+                    update_pv(info, ply, act);
                 }
             }
         }
@@ -390,22 +1023,29 @@ pub fn search<T: BitInt, const N: usize>(
         if score >= beta {
             bounds = Bounds::Lower; // CUT-node: beta-cutoff was performed
 
+            if is_quiet_check {
+                update_history(&mut info.check_history, team, act, history_bonus(depth));
+                for &quiet_check in &searched.quiet_checks {
+                    update_history(&mut info.check_history, team, quiet_check, -history_bonus(depth));
+                }
+            }
+
             if is_quiet {
                 update_history(&mut info.history, team, act, history_bonus(depth));
-                for &quiet in &quiets {
+                for &quiet in &searched.quiets {
                     update_history(&mut info.history, team, quiet, -history_bonus(depth));
                 }
 
                 if let Some(previous) = previous {
                     update_conthist(&mut info.conthist, team.next(), previous, team, act, history_bonus(depth));
-                    for &quiet in &quiets {
+                    for &quiet in &searched.quiets {
                         update_conthist(&mut info.conthist, team.next(), previous, team, quiet, -history_bonus(depth));
                     }
                 }
 
                 if let Some(previous) = two_ply {
                     update_conthist(&mut info.conthist, team, previous, team, act, history_bonus(depth));
-                    for &quiet in &quiets {
+                    for &quiet in &searched.quiets {
                         update_conthist(&mut info.conthist, team, previous, team, quiet, -history_bonus(depth));
                     }
                 }
@@ -418,9 +1058,13 @@ pub fn search<T: BitInt, const N: usize>(
                     }
                     info.killers[0][ply] = Some(act);
                 }
+
+                if let Some(previous) = previous {
+                    update_countermove(info, team, previous, act);
+                }
             } else {
                 update_history(&mut info.capture_history, team, act, history_bonus(depth));
-                for &noisy in &noisies {
+                for &noisy in &searched.noisies {
                     update_history(&mut info.capture_history, team, noisy, -history_bonus(depth));
                 }
             }
@@ -428,28 +1072,41 @@ pub fn search<T: BitInt, const N: usize>(
             break;
         }
 
+        if is_quiet_check {
+            searched.quiet_checks.push(act);
+        }
+
         if is_quiet {
-            quiets.push(act);
+            searched.quiets.push(act);
         } else {
-            noisies.push(act);
+            searched.noisies.push(act);
         }
     }
-    
+
+    info.searched[ply] = searched;
+
+    if !root_node {
+        info.hashes.pop();
+    }
+
     if info.abort { return 0; }
 
     if root_node && best_move.is_some() {
         info.best_move = best_move;
     }
 
-    info.tt[index] = Some(TtEntry { 
-        hash, 
-        best_move,
-        depth,
-        bounds,
-        score: best
-    });
-
-    info.hashes.pop();
+    // With moves left out the node's result isn't its real one
+    if (!root_node || (info.excluded_root.is_empty() && info.search_moves.is_empty())) && excluded.is_none() {
+        tt_store(info, TtEntry { 
+            hash, 
+            best_move,
+            depth,
+            bounds,
+            score: score_to_tt(best, ply),
+            was_pv: tt_pv,
+            generation: info.tt_generation
+        });
+    }
 
     best
 }
@@ -460,23 +1117,73 @@ pub fn create_search_info<T: BitInt, const N: usize>(board: &mut Board<T, N>) ->
 
     let mut info = SearchInfo {
         root_depth: 0,
+        root_moves: vec![],
+        root_moves_hash: None,
         best_move: None,
         capture_history: vec![ vec![ vec![ 0; squares ]; squares ]; 2 ],
         history: vec![ vec![ vec![ 0; squares ]; squares ]; 2 ],
+        check_history: vec![ vec![ vec![ 0; squares ]; squares ]; 2 ],
+        countermoves: vec![ vec![ vec![ None; squares ]; pieces ]; 2 ],
+        counter_killers: vec![ vec![ vec![ None; squares ]; pieces ]; 2 ],
         conthist: vec![ vec![ vec![ vec![ vec![ vec![ 0; squares ]; pieces ]; 2 ]; squares ]; pieces ]; 2 ],
-        quiet_lmr: vec![ vec![ 0; 100 ]; 256 ],
-        noisy_lmr: vec![ vec![ 0; 100 ]; 256 ],
-        pv_table: vec![],
+        lmr: vec![ vec![ vec![ 0; 100 ]; 256 ]; 8 ],
+        pv_table: vec![ vec![]; MAX_PLY ],
+        root_pv: vec![],
         hashes: vec![],
-        killers: vec![],
-        mobility: vec![ None; 100 ],
-        zobrist: board.game.rules.gen_zobrist(board, 64),
-        tt_size: 1_000_000,
-        tt: vec![ None; 1_000_000 ],
+        killers: vec![ vec![ None; KILLER_PLIES ]; MAX_KILLERS ],
+        killer_root: 0,
+        mobility: vec![ None; MAX_PLY ],
+        threats: vec![ None; MAX_PLY ],
+        evals: vec![ MIN; MAX_PLY ],
+        eval_cache: vec![ None; MAX_PLY ],
+        halfmove: vec![ 0; MAX_PLY ],
+        searched: vec![ SearchedMoves::default(); MAX_PLY ],
+        root_halfmove: 0,
+        last_search_score: None,
+        game_searches: 0,
+        contempt: 0,
+        root_team: Team::White,
+        endgame: false,
+        analysis: false,
+        root_noise: 0,
+        root_noise_plies: ROOT_NOISE_PLIES,
+        root_noise_seed: 0,
+        params: create_search_params(),
+        eval_params: create_eval_params(),
+        zobrist: Arc::new(board.game.rules.gen_zobrist(board, 64)),
+        tt_replacement: Replacement::Always,
+        tt_generation: 0,
+        tt: Arc::new(create_tt(tt_entries(DEFAULT_HASH))),
+        threads: 1,
+        shared_stop: Arc::new(AtomicBool::new(false)),
+        shared_nodes: Arc::new(AtomicU64::new(0)),
+        helper: false,
+        reported_nodes: 0,
+        report_currmove: false,
+        ablated: vec![],
         nodes: 0,
         score: 0,
         abort: false,
-        time_to_abort: u128::MAX
+        time_to_abort: u128::MAX,
+        search_start: 0,
+        max_nodes: u64::MAX,
+        max_depth: MAX_DEPTH,
+        stop: StopReason::Depth,
+        stats: create_search_stats(),
+        next_time_check: 0,
+        seldepth: 0,
+        generic: false,
+        royal_pieces: VARIANTS[0].royal_pieces.to_vec(),
+        filler_pieces: VARIANTS[0].filler_pieces.to_vec(),
+        minor_pieces: VARIANTS[0].minor_pieces.to_vec(),
+        san_output: false,
+        kibitz: false,
+        multipv: 1,
+        excluded_root: vec![],
+        search_moves: vec![],
+        show_wdl: false,
+        last_kibitz: 0,
+        nnue: None
     };
 
     fn compute_lmr(base: f64, divisor: f64, index: usize, depth: usize) -> i32 {
@@ -484,18 +1191,68 @@ pub fn create_search_info<T: BitInt, const N: usize>(board: &mut Board<T, N>) ->
         (r * 256.) as i32
     }
 
-    for index in 0..256 {
-        for depth in 0..100 {
-            info.noisy_lmr[index][depth] = compute_lmr(-0.25, 3., index, depth);
-            info.quiet_lmr[index][depth] = compute_lmr(0.75, 2.5, index, depth);
+    for noisy in [false, true] {
+        for pv in [false, true] {
+            for improving in [false, true] {
+                let (mut base, divisor) = if noisy { (-0.25, 3.) } else { (0.75, 2.5) };
+
+                // PV nodes are reduced less, nodes whose eval is getting worse more
+                if pv { base -= 0.5; }
+                if !improving { base += 0.25; }
+
+                let table = &mut info.lmr[lmr_class(noisy, pv, improving)];
+                for index in 0..256 {
+                    for depth in 0..100 {
+                        table[index][depth] = compute_lmr(base, divisor, index, depth);
+                    }
+                }
+            }
         }
-    }    
+    }
 
     info
 }
 
-pub fn aspiration<T: BitInt, const N: usize>(info: &mut SearchInfo, board: &mut Board<T, N>, depth: i32) -> i32 {
-    let max_window_size = ROOK;
+// Long searches say which root move they're on, short ones would only flood the GUI
+pub const CURRMOVE_AFTER: u128 = 3000;
+
+fn report_currmove<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, depth: i32, action: Action, index: usize) {
+    if current_time_millis() - info.search_start >= CURRMOVE_AFTER {
+        println!("info depth {} currmove {} currmovenumber {}", depth, board.display_uci_action(action), index + 1);
+    }
+}
+
+// The UCI `score` field, forced mates as a distance in moves
+fn display_score(score: i32) -> String {
+    match to_score(score) {
+        Score::Cp(score) => format!("cp {}", score),
+        Score::Mate(moves) => format!("mate {}", moves)
+    }
+}
+
+// GUIs expect a bound-tagged score whenever the window fails and gets re-searched. A fail high's
+// line starts with the move that failed high; a fail low has no line of its own, so the last
+// iteration's stands. Either is replayed and cut at the first illegal move, as for the main line
+fn report_bound<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, depth: i32, score: i32, bound: Bounds) {
+    let time = ((current_time_millis() - info.search_start) as u64).max(1);
+    let (bound, line) = match bound {
+        Bounds::Lower if !info.pv_table[0].is_empty() => ("lowerbound", &info.pv_table[0]),
+        Bounds::Lower => ("lowerbound", &info.root_pv),
+        Bounds::Upper => ("upperbound", &info.root_pv),
+        Bounds::Exact => return
+    };
+
+    let pv: Vec<String> = complete_pv(board, info, line, line.len()).into_iter()
+        .map(|action| board.display_uci_action(action))
+        .collect();
+
+    println!(
+        "info depth {} seldepth {} score {} {} time {} nodes {} nps {} pv {}",
+        depth, info.seldepth, display_score(score), bound, time, total_nodes(info), smoothed_nps(&info.stats, time, total_nodes(info)), pv.join(" ")
+    );
+}
+
+pub fn aspiration<T: BitInt, const N: usize>(info: &mut SearchInfo, board: &mut Board<T, N>, depth: i32, report: bool) -> i32 {
     let mut delta = 30;
     let (mut alpha, mut beta) = if depth >= 5 {
         (info.score - delta, info.score + delta)
@@ -504,83 +1261,662 @@ pub fn aspiration<T: BitInt, const N: usize>(info: &mut SearchInfo, board: &mut
     };
 
     loop {
-        let score = search(board, info, depth, 0, alpha, beta, true);
+        let score = search(board, info, depth, 0, alpha, beta, true, None);
         if info.abort {
             return 0;
         }
 
         if score <= alpha && score > MIN {
+            if report {
+                report_bound(board, info, depth, score, Bounds::Upper);
+            }
+
             alpha = (score - delta).max(MIN);
         } else if score >= beta && score < MAX {
+            if report {
+                report_bound(board, info, depth, score, Bounds::Lower);
+            }
+
             beta = (score + delta).min(MAX);
         } else {
             return score;
         }
 
-        delta *= 2;
-        if delta >= max_window_size {
-            delta = MAX;
+        // Widen gradually, the window only opens fully once it has grown past mate scores
+        delta += delta / 2;
+    }
+}
+
+pub fn tt_move<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo) -> Option<Action> {
+    let hash = board.game.rules.hash(board, &info.zobrist);
+
+    tt_probe(info, hash).and_then(|entry| entry.best_move)
+}
+
+// Plies without a capture or pawn move before the game is drawn
+pub const FIFTY_MOVE_PLIES: usize = 100;
+
+// Where a line runs into a draw: a position already in `hashes` (the game so far) or earlier in the
+// line repeats, as the search scores it, or the halfmove clock runs out, which the search doesn't
+// score and is only reported
+fn pv_draw<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, line: &[Action]) -> Option<(&'static str, usize)> {
+    let mut states = vec![];
+    let mut seen = info.hashes.clone();
+    let mut halfmove = info.root_halfmove;
+    let mut draw = None;
+
+    for (ply, &action) in line.iter().enumerate() {
+        halfmove = if resets_halfmove(board, info, action) { 0 } else { halfmove + 1 };
+        states.push(board.play(action));
+
+        let hash = board.game.rules.hash(board, &info.zobrist);
+        if seen.contains(&hash) {
+            draw = Some(("repetition", ply + 1));
+            break;
+        }
+
+        if halfmove >= FIFTY_MOVE_PLIES {
+            draw = Some(("fifty-move rule", ply + 1));
+            break;
         }
+
+        seen.push(hash);
+    }
+
+    while let Some(state) = states.pop() {
+        board.restore(state);
     }
+
+    draw
 }
 
-pub fn iterative_deepening<T: BitInt, const N: usize>(uci: &Uci, info: &mut SearchInfo, board: &mut Board<T, N>, soft_time: u64, hard_time: u64) {
-    let start = current_time_millis();
+// Replays `line`, cutting it at the first illegal move, then carries on from the TT; a
+// position coming up twice ends it, as TT moves can cycle
+pub fn complete_pv<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, line: &[Action], max_len: usize) -> Vec<Action> {
+    let mut pv = vec![];
+    let mut states = vec![];
+    let mut seen = vec![ board.game.rules.hash(board, &info.zobrist) ];
+
+    loop {
+        let next = match line.get(pv.len()) {
+            Some(&action) => Some(action),
+            None if pv.len() < max_len => tt_move(board, info),
+            None => None
+        };
+
+        let action = match next {
+            Some(action) if legal_actions(board).contains(&action) => action,
+            _ => break
+        };
+
+        states.push(board.play(action));
+        pv.push(action);
+
+        let hash = board.game.rules.hash(board, &info.zobrist);
+        if seen.contains(&hash) {
+            break;
+        }
+        seen.push(hash);
+    }
+
+    while let Some(state) = states.pop() {
+        board.restore(state);
+    }
+
+    pv
+}
+
+fn update_root_move(info: &mut SearchInfo, ply: usize, action: Action, score: i32, exact: bool, nodes: u64) {
+    let child_pv = info.pv_table.get(ply + 1).cloned().unwrap_or_default();
+    let root_move = match info.root_moves.iter_mut().find(|root_move| root_move.action == action) {
+        Some(root_move) => root_move,
+        None => return
+    };
+
+    root_move.nodes += nodes;
+    if exact {
+        root_move.score = score;
+        root_move.pv = vec![ action ];
+        root_move.pv.extend(child_pv);
+    } else {
+        root_move.score = MIN;
+    }
+}
+
+fn start_iteration(info: &mut SearchInfo) {
+    if info.root_depth > 1 {
+        decay_history(info);
+    }
+
+    for root_move in &mut info.root_moves {
+        root_move.previous_score = root_move.score;
+        root_move.score = MIN;
+    }
+}
+
+// Stable, so moves that failed low keep the order they were searched in
+fn sort_root_moves(info: &mut SearchInfo) {
+    info.root_moves.sort_by(|a, b| b.score.cmp(&a.score));
+}
+
+fn reset_search<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &mut SearchInfo, start: u128, hard_time: u64) {
+    let root_ply = board.history.len();
+    info.root_team = board.state.moving_team;
+    let root_hash = board.game.rules.hash(board, &info.zobrist);
+
+    if info.root_moves_hash != Some(root_hash) || info.root_moves.is_empty() {
+        info.root_moves = legal_actions(board).into_iter()
+            .map(|action| RootMove { action, score: MIN, previous_score: MIN, nodes: 0, pv: vec![ action ] })
+            .collect();
+        info.root_moves_hash = Some(root_hash);
+    }
+
+    // The full list is built again for the next search
+    if !info.search_moves.is_empty() {
+        info.root_moves.retain(|root| info.search_moves.contains(&root.action));
+        info.root_moves_hash = None;
+    }
+
     info.time_to_abort = start + hard_time as u128;
+    info.search_start = start;
     info.abort = false;
     info.nodes = 0;
-    info.killers = vec![ vec![ None; 100 ]; MAX_KILLERS ];
+    info.next_time_check = 0;
+    info.stop = StopReason::Depth;
+    info.seldepth = 0;
+    info.best_move = None;
+    info.root_pv.clear();
+    info.tt_generation = info.tt_generation.wrapping_add(1);
+    shift_killers(info, root_ply);
+
+    // Hashes recorded for some other game would show up as phantom repetitions
+    if info.hashes.len() != root_ply + 1 || info.hashes.last() != Some(&root_hash) {
+        info.hashes = vec![ root_hash ];
+    }
+
+    // Per-ply stacks are looked back through (ie. mobility), so nothing may carry over from the last search
+    info.mobility.fill(None);
+    info.evals.fill(MIN);
+    info.eval_cache.fill(None);
+    info.halfmove.fill(0);
+    info.halfmove[0] = info.root_halfmove;
+    info.threats.fill(None);
+    for pv in &mut info.pv_table {
+        pv.clear();
+    }
+}
+
+fn create_result<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, depth: i32, start: u128) -> Option<SearchResult> {
+    let best = info.best_move?;
+
+    // The PV stack can come up short (ie. a TT cutoff right below the root), so it's checked
+    // by replaying it and extended from the TT up to the iteration's depth
+    let single = [ best ];
+    let line = if info.root_pv.first() == Some(&best) { info.root_pv.as_slice() } else { &single[..] };
+    let mut pv = complete_pv(board, info, line, (depth.max(1) as usize).min(MAX_DEPTH as usize));
+    if pv.is_empty() {
+        pv.push(best);
+    }
+    debug_assert!(pv_is_legal(board, &pv), "PV replays legally");
+
+    Some(SearchResult {
+        best,
+        ponder: pv.get(1).copied(),
+        score: to_score(info.score),
+        bound: Bounds::Exact,
+        depth,
+        seldepth: info.seldepth,
+        nodes: total_nodes(info),
+        pv,
+        root_moves: info.root_moves.clone(),
+        stop: info.stop,
+        time: (current_time_millis() - start) as u64
+    })
+}
+
+// Each line after the first is the best root move left once the lines above it are excluded;
+// their scores and PVs end up in the root moves, which are left sorted with the lines on top
+fn search_extra_lines<T: BitInt, const N: usize>(info: &mut SearchInfo, board: &mut Board<T, N>, depth: i32) {
+    let lines = info.multipv.min(info.root_moves.len());
+    let best_move = info.best_move;
+
+    info.excluded_root = best_move.into_iter().collect();
+    while info.excluded_root.len() < lines {
+        info.best_move = None;
+        search(board, info, depth, 0, MIN, MAX, true, None);
+
+        match info.best_move {
+            Some(found) if !info.abort => info.excluded_root.push(found),
+            _ => break
+        }
+    }
+
+    info.excluded_root.clear();
+    info.best_move = best_move;
+    sort_root_moves(info);
+}
+
+// Expected result per mille from the side to move's point of view
+pub const WDL_MIDPOINT: f64 = 200.;
+pub const WDL_SCALE: f64 = 80.;
+
+fn display_wdl(score: i32) -> String {
+    let win_rate = |cp: f64| 1000. / (1. + (-(cp - WDL_MIDPOINT) / WDL_SCALE).exp());
+    let (win, loss) = match to_score(score) {
+        Score::Mate(moves) if moves > 0 => (1000, 0),
+        Score::Mate(_) => (0, 1000),
+        Score::Cp(cp) => (win_rate(cp as f64).round() as i32, win_rate(-cp as f64).round() as i32)
+    };
+
+    format!("{} {} {}", win, 1000 - win - loss, loss)
+}
+
+// The first line is the search's own result, with the PV checked for legality by `create_result`
+fn report_lines<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, result: &SearchResult, time: u64) {
+    let lines = info.multipv.min(info.root_moves.len()).max(1);
+
+    for index in 0..lines {
+        let (score, line) = match index {
+            0 => (info.score, &result.pv),
+            _ => (info.root_moves[index].score, &info.root_moves[index].pv)
+        };
+
+        let multipv = if info.multipv > 1 { format!(" multipv {}", index + 1) } else { String::new() };
+        let wdl = if info.show_wdl { format!(" wdl {}", display_wdl(score)) } else { String::new() };
+        let pv: Vec<String> = line.iter().map(|&action| board.display_uci_action(action)).collect();
+
+        println!(
+            "info depth {} seldepth {}{} score {}{} time {} nodes {} nps {} hashfull {} pv {}",
+            result.depth, info.seldepth, multipv, display_score(score), wdl, time, result.nodes,
+            smoothed_nps(&info.stats, time, result.nodes), hashfull(info), pv.join(" ")
+        );
+
+        // A drawn line's score is just contempt, easily read as the position being equal
+        if info.analysis {
+            if let Some((reason, plies)) = pv_draw(board, info, line) {
+                println!("info string{} draw by {} after {} plies of the pv", multipv, reason, plies);
+            }
+        }
+    }
+}
+
+pub const KIBITZ_INTERVAL: u128 = 2000;
+// Searches after leaving book whose scores are averaged into `last_search_score`
+pub const SMOOTHED_SEARCHES: u32 = 3;
 
-    for depth in 1..100 {
+fn display_eval(score: i32) -> String {
+    match to_score(score) {
+        Score::Cp(score) => format!("{:+.2}", score as f64 / 100.),
+        Score::Mate(moves) if moves > 0 => format!("mate in {}", moves),
+        Score::Mate(moves) => format!("mated in {}", -moves)
+    }
+}
+
+// Commentary for streams and bots, throttled so a long `go infinite` doesn't flood the chat
+fn kibitz<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &mut SearchInfo, result: &SearchResult) {
+    let now = current_time_millis();
+    if now - info.last_kibitz < KIBITZ_INTERVAL {
+        return;
+    }
+    info.last_kibitz = now;
+
+    let mut summary = format!(
+        "info string kibitz best line: {}, eval {}, depth {}",
+        display_san_line(board, &result.pv).join(" "), display_eval(info.score), result.depth
+    );
+
+    if let Some(threat) = biggest_threat(board, info) {
+        let state = board.play_null();
+        summary.push_str(&format!(", threat: {}", display_san(board, threat)));
+        board.restore(state);
+    }
+
+    println!("{}", summary);
+}
+
+// Iterative deepening without any UCI output, used by `bench` and `play`
+pub fn search_to_depth<T: BitInt, const N: usize>(
+    info: &mut SearchInfo, 
+    board: &mut Board<T, N>, 
+    max_depth: i32,
+    soft_time: u64,
+    hard_time: u64
+) -> Option<SearchResult> {
+    let start = current_time_millis();
+    info.search_moves.clear();
+    reset_search(board, info, start, hard_time);
+
+    let mut completed = 0;
+
+    for depth in 1..=max_depth {
         info.root_depth = depth;
-        info.pv_table = vec![ vec![]; 100 ];
+        start_iteration(info);
 
-        let score = aspiration(info, board, depth);
+        let score = aspiration(info, board, depth, false);
         if info.abort {
+            info.stop = abort_reason(info);
             break;
         }
 
         info.score = score;
+        info.root_pv = info.pv_table[0].clone();
+        sort_root_moves(info);
+        completed = depth;
 
-        let current_time = current_time_millis();
+        if info.nodes >= info.max_nodes {
+            info.stop = StopReason::Nodes;
+            break;
+        }
 
-        // PV Tables are still bugged, so temporarily disabling them.
-        /*let history = restore_perfectly(board);
-        let past_moves = board.history.clone();
-        let team = board.state.moving_team.clone();
+        if current_time_millis() - start > soft_time as u128 {
+            info.stop = StopReason::SoftTime;
+            break;
+        }
+    }
 
-        let mut pv_acts: Vec<String> = vec![];
-        for act in info.pv_table[0].clone() {
-            if let ActionRecord::Action(act) = act {
-                if board.state.mailbox[act.from as usize] == 0 {
-                    // Invalid PV end early
-                    break;
-                }
+    create_result(board, info, completed, start)
+}
 
-                pv_acts.push(board.display_uci_action(act));
-                board.play(act);
+// A Lazy SMP helper deepens the same root on its own copy of the search state, sharing only the TT,
+// until the main thread is done; odd helpers start a ply deeper to spread the threads over two depths
+fn helper_search<T: BitInt, const N: usize>(mut info: SearchInfo, mut board: Board<T, N>, index: usize) {
+    for depth in (1 + index % 2) as i32..=info.max_depth.clamp(1, MAX_DEPTH) {
+        info.root_depth = depth;
+        start_iteration(&mut info);
+
+        let score = aspiration(&mut info, &mut board, depth, false);
+        if info.abort {
+            break;
+        }
+
+        info.score = score;
+        sort_root_moves(&mut info);
+    }
+
+    report_helper_nodes(&mut info);
+}
+
+// Times come from `time::clock_times` and whatever adjusts them; everything else is read from `limits`
+// The per-`go` limits, all of which have to be set again for each search so none carry over
+pub fn apply_limits<T: BitInt, const N: usize>(info: &mut SearchInfo, board: &mut Board<T, N>, limits: &Limits) {
+    info.max_nodes = limits.nodes.unwrap_or(u64::MAX);
+    info.max_depth = max_depth(limits);
+    info.analysis = limits.infinite;
+    info.search_moves = search_moves(board, limits);
+}
+
+pub fn iterative_deepening<T: BitInt, const N: usize>(
+    info: &mut SearchInfo,
+    board: &mut Board<T, N>,
+    limits: &Limits,
+    soft_time: u64,
+    hard_time: u64
+) -> Option<SearchResult> where Board<T, N>: Clone + Send {
+    let start = current_time_millis();
+    apply_limits(info, board, limits);
+    reset_search(board, info, start, hard_time);
+    info.last_kibitz = 0;
+    info.stats.iterations.clear();
+    info.stats.nps_samples.clear();
+    info.report_currmove = true;
+    info.shared_stop = Arc::new(AtomicBool::new(false));
+    info.shared_nodes = Arc::new(AtomicU64::new(0));
+
+    let completed = thread::scope(|scope| {
+        for index in 1..info.threads {
+            let (mut helper, helper_board) = (info.clone(), board.clone());
+            (helper.helper, helper.reported_nodes, helper.report_currmove) = (true, 0, false);
+            let spawned = thread::Builder::new()
+                .stack_size(SEARCH_STACK)
+                .spawn_scoped(scope, move || helper_search(helper, helper_board, index));
+
+            if let Err(error) = spawned {
+                println!("info string failed to start search thread {}: {}", index, error);
             }
         }
 
-        board.state.restore(history);
-        board.history = past_moves;
-        board.state.moving_team = team;*/
+        let completed = deepen(info, board, soft_time, start);
+        info.shared_stop.store(true, atomic::Ordering::Relaxed);
 
-        let mut time = (current_time - start) as u64;
-        if time == 0 { time = 1; }
+        completed
+    });
 
-        uci.info(Info {
-            depth: Some(depth as u32),
-            score_cp: Some(info.score),
-            time: Some(time),
-            nodes: Some(info.nodes),
-            nps: Some(info.nodes / time * 1000),
-            pv: info.best_move.map(|el| vec![ board.display_uci_action(el) ]), //Some(pv_acts),
-            ..Default::default()
+    if completed > 0 {
+        // The first scores out of book jump around, averaging them keeps time allocation from following
+        info.last_search_score = Some(match info.last_search_score {
+            Some(previous) if info.game_searches < SMOOTHED_SEARCHES => (previous + info.score) / 2,
+            _ => info.score
         });
+        info.game_searches += 1;
+    }
+
+    create_result(board, info, completed, start)
+}
+
+// The main thread's iterations, reported as they complete; returns the last completed depth
+fn deepen<T: BitInt, const N: usize>(
+    info: &mut SearchInfo,
+    board: &mut Board<T, N>,
+    soft_time: u64,
+    start: u128
+) -> i32 {
+    let mut completed = 0;
+    let mut depth = 1;
+    let mut previous_score = None;
 
-        if time > soft_time {
-            break;   
+    while depth <= info.max_depth.clamp(1, MAX_DEPTH) {
+        info.root_depth = depth;
+        start_iteration(info);
+
+        let score = aspiration(info, board, depth, true);
+        if !info.abort {
+            info.score = score;
+            info.root_pv = info.pv_table[0].clone();
+            sort_root_moves(info);
+            search_extra_lines(info, board, depth);
+        }
+
+        if info.abort {
+            // MultiPV changed mid-analysis, so the iterations start over with the new number of lines
+            if let Some(multipv) = take_pending_multipv() {
+                info.multipv = multipv;
+                info.abort = false;
+                depth = 1;
+                continue;
+            }
+
+            info.stop = abort_reason(info);
+            break;
         }
+
+        completed = depth;
+
+        let result = match create_result(board, info, depth, start) {
+            Some(result) => result,
+            None => break
+        };
+        let time = result.time.max(1);
+        record_iteration(&mut info.stats, depth, info.nodes, result.time);
+        record_nps_sample(&mut info.stats, time, result.nodes);
+
+        report_lines(board, info, &result, time);
+
+        if info.san_output {
+            println!("info string san {}", display_san_line(board, &result.pv).join(" "));
+        }
+
+        if info.kibitz {
+            kibitz(board, info, &result);
+        }
+
+        if info.nodes >= info.max_nodes {
+            info.stop = StopReason::Nodes;
+            break;
+        }
+
+        if time > endgame_soft_time(info, soft_time, previous_score) {
+            info.stop = StopReason::SoftTime;
+            break;
+        }
+
+        previous_score = Some(info.score);
+        depth += 1;
+    }
+
+    completed
+}
+
+#[cfg(test)]
+mod tests {
+    use chessing::chess::Chess;
+
+    use super::*;
+
+    // Pieces en prise on both sides, so quiescence alone has far more nodes than any limit below
+    const CAPTURE_HEAVY: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+
+    fn probe_info(board: &mut Board<u64, 6>) -> SearchInfo {
+        let mut info = create_search_info(board);
+        prepare_probe(&mut info);
+        info.next_time_check = 0;
+        info
+    }
+
+    #[test]
+    fn quiescence_stops_at_the_node_limit() {
+        let mut board = Chess::create::<u64, 6>().load(CAPTURE_HEAVY);
+        let mut info = probe_info(&mut board);
+        info.max_nodes = 50;
+
+        quiescence(&mut board, &mut info, 0, MIN, MAX);
+
+        assert!(info.abort);
+        assert_eq!(info.nodes, info.max_nodes);
+    }
+
+    #[test]
+    fn quiescence_stops_at_the_first_clock_check_past_the_deadline() {
+        let mut board = Chess::create::<u64, 6>().load(CAPTURE_HEAVY);
+        let mut info = probe_info(&mut board);
+        info.time_to_abort = current_time_millis();
+        info.next_time_check = 1;
+
+        quiescence(&mut board, &mut info, 0, MIN, MAX);
+
+        // The clock is next read after a node, and nothing is searched once it has been
+        assert!(info.abort);
+        assert!(info.nodes <= TIME_CHECK_NODES, "{} nodes past the deadline", info.nodes);
+    }
+
+    // Searches started as deep in the tree as the per-ply stacks go, on a thread sized like the
+    // real ones; the `MAX_PLY` guard has to stop them before anything is indexed past the end
+    #[test]
+    fn search_at_max_ply_stays_in_bounds() {
+        let searched = thread::Builder::new().stack_size(SEARCH_STACK).spawn(|| {
+            let mut board = Chess::create::<u64, 6>().load(CAPTURE_HEAVY);
+            let mut info = create_search_info(&mut board);
+            reset_search(&mut board, &mut info, current_time_millis(), u64::MAX);
+            info.max_nodes = 20_000;
+
+            for (depth, ply) in [ (MAX_PLY as i32, 0), (8, MAX_PLY - 4), (8, MAX_PLY - 1) ] {
+                info.root_depth = depth;
+                info.abort = false;
+                info.nodes = 0;
+
+                let score = search(&mut board, &mut info, depth, ply, MIN, MAX, true, None);
+                assert!((MIN..=MAX).contains(&score));
+            }
+        });
+
+        assert!(searched.expect("Search thread starts").join().is_ok());
+    }
+
+    // Knights out and back lands on the root again, which has to be a draw even with nothing
+    // but the root itself recorded for the game
+    #[test]
+    fn repeating_the_root_is_a_draw() {
+        let mut board = Chess::create::<u64, 6>().default();
+        let mut info = create_search_info(&mut board);
+        info.contempt = 37;
+        reset_search(&mut board, &mut info, current_time_millis(), u64::MAX);
+
+        for name in [ "g1f3", "g8f6", "f3g1", "f6g8" ] {
+            let action = legal_actions(&mut board).into_iter()
+                .find(|&action| board.display_uci_action(action) == name)
+                .expect("Knight moves are legal");
+            board.play(action);
+        }
+
+        let score = search(&mut board, &mut info, 3, 4, MIN, MAX, false, None);
+
+        assert_eq!(score, draw_score(&mut board, &info));
+        assert_eq!(score, -37);
+        assert_eq!(info.hashes.len(), 1);
+    }
+
+    // Qxd6 loses the queen to exd6, Ra8+ is a quiet check; the check is tried first
+    #[test]
+    fn quiet_checks_go_before_losing_captures() {
+        let mut board = Chess::create::<u64, 6>().load("4k3/4p3/3p4/8/8/8/8/R2QK3 w - - 0 1");
+        let mut info = create_search_info(&mut board);
+        let actions = legal_actions(&mut board);
+
+        let order: Vec<String> = sort_actions(&mut board, &mut info, 0, actions, None, None, None).iter()
+            .map(|&ScoredAction(action, _)| board.display_uci_action(action))
+            .collect();
+        let position = |name: &str| order.iter().position(|action| action == name).expect("Both moves are legal");
+
+        assert!(position("a1a8") < position("d1d6"), "{:?}", order);
     }
-}
\ No newline at end of file
+
+    // Plays the line out and back, checking each move is legal where it's played
+    fn assert_legal_line(board: &mut Board<u64, 6>, info: &SearchInfo, line: &[Action]) {
+        let hash = board.game.rules.hash(board, &info.zobrist);
+        let mut states = vec![];
+
+        for &action in line {
+            assert!(legal_actions(board).contains(&action), "{} isn't legal in the pv", board.display_uci_action(action));
+            states.push(board.play(action));
+        }
+
+        while let Some(state) = states.pop() {
+            board.restore(state);
+        }
+
+        assert_eq!(board.game.rules.hash(board, &info.zobrist), hash);
+    }
+
+    #[test]
+    fn bench_pvs_replay_legally() {
+        let chess = Chess::create::<u64, 6>();
+
+        for fen in VARIANTS[0].positions {
+            let mut board = chess.load(fen);
+            let mut info = create_search_info(&mut board);
+
+            // Each search starts from the last one's tables, like the iterations of one search do
+            for depth in 1..=5 {
+                let result = search_to_depth(&mut info, &mut board, depth, u64::MAX, u64::MAX).expect("Bench positions have moves");
+
+                assert_eq!(result.pv.first(), Some(&result.best));
+                assert_legal_line(&mut board, &info, &result.pv);
+            }
+        }
+    }
+
+    // One `SearchInfo` carried from position to position, as a GUI's engine would; a PV left from
+    // the last search would be illegal in the next
+    #[test]
+    fn pvs_dont_leak_between_searches() {
+        let chess = Chess::create::<u64, 6>();
+        let mut board = chess.load(VARIANTS[0].positions[0]);
+        let mut info = create_search_info(&mut board);
+
+        for fen in VARIANTS[0].positions.iter().chain(VARIANTS[0].positions) {
+            board = chess.load(fen);
+            let result = search_to_depth(&mut info, &mut board, 4, u64::MAX, u64::MAX).expect("Bench positions have moves");
+
+            assert_legal_line(&mut board, &info, &result.pv);
+        }
+    }
+}
@@ -2,7 +2,7 @@ use chessing::{bitboard::{BitBoard, BitInt}, game::{action::{Action, ActionRecor
 
 use crate::eval::MATERIAL;
 
-use super::{is_noisy, SearchInfo, TtEntry};
+use super::{gives_check, is_noisy, is_underpromotion, promotion_piece, see::see, SearchInfo, TtEntry, MAX_PLY};
 
 // [team][sq][sq]
 pub type History = Vec<Vec<Vec<i32>>>;
@@ -10,17 +10,71 @@ pub type History = Vec<Vec<Vec<i32>>>;
 // [team][piece][sq][team][piece][sq]
 pub type ContinuationHistory = Vec<Vec<Vec<Vec<Vec<Vec<i32>>>>>>;
 
+// [team][piece][sq] of the previous move, to the quiet that refuted it; also used for
+// counter-killers, the refutation the current countermove displaced
+pub type CounterMoves = Vec<Vec<Vec<Option<Action>>>>;
+
+// Tables are sized from the board when `SearchInfo` is created, every lookup goes through
+// the helpers below so a variant with more squares or pieces trips here in debug builds
+fn checked_index(index: usize, len: usize, what: &str) -> usize {
+    debug_assert!(index < len, "{} {} is outside a table of {}", what, index, len);
+    index
+}
+
+// (from, to) into a `History`
+pub fn butterfly_index(history: &History, action: Action) -> (usize, usize) {
+    let squares = history[0].len();
+    (checked_index(action.from as usize, squares, "square"), checked_index(action.to as usize, squares, "square"))
+}
+
+// (piece, to), the context continuation history and countermoves are keyed by
+pub fn piece_to_index(pieces: usize, squares: usize, action: Action) -> (usize, usize) {
+    (checked_index(action.piece as usize, pieces, "piece"), checked_index(action.to as usize, squares, "square"))
+}
+
+pub fn history_value(history: &History, team: Team, action: Action) -> i32 {
+    let (from, to) = butterfly_index(history, action);
+    history[team.index()][from][to]
+}
+
+fn conthist_entry(conthist: &mut ContinuationHistory, prio: Team, previous: Action, team: Team, action: Action) -> &mut i32 {
+    let (pieces, squares) = (conthist[0].len(), conthist[0][0].len());
+    let (prio_piece, prio_to) = piece_to_index(pieces, squares, previous);
+    let (piece, to) = piece_to_index(pieces, squares, action);
+
+    &mut conthist[prio.index()][prio_piece][prio_to][team.index()][piece][to]
+}
+
+pub fn conthist_value(conthist: &ContinuationHistory, prio: Team, previous: Action, team: Team, action: Action) -> i32 {
+    let (pieces, squares) = (conthist[0].len(), conthist[0][0].len());
+    let (prio_piece, prio_to) = piece_to_index(pieces, squares, previous);
+    let (piece, to) = piece_to_index(pieces, squares, action);
+
+    conthist[prio.index()][prio_piece][prio_to][team.index()][piece][to]
+}
+
+// Slot for the reply to `previous`, played by the other side to `team`
+pub fn countermove_entry(countermoves: &mut CounterMoves, team: Team, previous: Action) -> &mut Option<Action> {
+    let (pieces, squares) = (countermoves[0].len(), countermoves[0][0].len());
+    let (piece, to) = piece_to_index(pieces, squares, previous);
+
+    &mut countermoves[team.next().index()][piece][to]
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct ScoredAction(pub Action, pub i32);
 
+// A qsearch capture with its ordering score and SEE value
+#[derive(Clone, Debug, Copy)]
+pub struct ScoredCapture(pub Action, pub i32, pub i32);
+
 pub fn mvv_lva<T: BitInt, const N: usize>(
     board: &mut Board<T, N>, 
     action: Action,
 ) -> i32 {
     let mut score = 1000;
-    if action.piece == 0 && action.info >= 3 {
-        // Pawn Promotion
-        score += MATERIAL[(action.info - 2) as usize] - MATERIAL[0];
+    if let Some(promotion) = promotion_piece(action) {
+        score += MATERIAL[promotion] - MATERIAL[0];
     }
 
     if let Some(victim_type) = board.piece_at(action.to) {
@@ -43,28 +97,81 @@ pub fn history_bonus(depth: i32) -> i32 {
 }
 
 pub fn update_history(history: &mut History, team: Team, action: Action, bonus: i32) {
-    let from = action.from as usize;
-    let to = action.to as usize;
+    let (from, to) = butterfly_index(history, action);
     let clamped_bonus = bonus.clamp(MIN_HISTORY, MAX_HISTORY);
+    let entry = &mut history[team.index()][from][to];
 
-    history[team.index()][from][to]
-        += clamped_bonus - history[team.index()][from][to] * clamped_bonus.abs() / MAX_HISTORY;
+    *entry += clamped_bonus - *entry * clamped_bonus.abs() / MAX_HISTORY;
 }
 
 pub fn update_conthist(conthist: &mut ContinuationHistory, prio: Team, previous: Action, team: Team, action: Action, bonus: i32) {
-    let prio_piece = previous.piece as usize;
-    let prio_to = previous.to as usize;
-
-    let piece = action.piece as usize;
-    let to = action.to as usize;
     let clamped_bonus = bonus.clamp(MIN_HISTORY, MAX_HISTORY);
+    let entry = conthist_entry(conthist, prio, previous, team, action);
 
-    conthist[prio.index()][prio_piece][prio_to][team.index()][piece][to]
-        += clamped_bonus - conthist[prio.index()][prio_piece][prio_to][team.index()][piece][to] * clamped_bonus.abs() / MAX_HISTORY;
+    *entry += clamped_bonus - *entry * clamped_bonus.abs() / MAX_HISTORY;
+}
+
+// Scores from the shallow first iterations are mostly noise, so every iteration after the first
+// shrinks what's there before adding to it; by depth 20 they're a small share of the total
+pub fn decay_history(info: &mut SearchInfo) {
+    let keep = info.params.history_decay;
+    if keep >= 1024 {
+        return;
+    }
+
+    let decay = |value: &mut i32| *value = *value * keep / 1024;
+
+    for table in [ &mut info.history, &mut info.capture_history, &mut info.check_history ] {
+        table.iter_mut().flatten().flatten().for_each(decay);
+    }
+
+    info.conthist.iter_mut().flatten().flatten().flatten().flatten().flatten().for_each(decay);
 }
 
 pub const HIGH_PRIORITY: i32 = 2i32.pow(28);
+// Quiet checks go after the captures that don't lose material but before the remaining quiets
+pub const QUIET_CHECK_PRIORITY: i32 = 2i32.pow(27);
+// Captures that lose material by SEE, after the quiet checks
+pub const LOSING_CAPTURE_PRIORITY: i32 = 2i32.pow(26);
+pub const THREAT_ESCAPE_BONUS: i32 = 200;
+pub const COUNTERMOVE_BONUS: i32 = 80;
+pub const COUNTER_KILLER_BONUS: i32 = 40;
+// Puts captures that hold their material above every losing one in qsearch
+pub const GOOD_CAPTURE_TIER: i32 = 2i32.pow(16);
+
+fn refutation(table: &CounterMoves, team: Team, previous: Option<Action>) -> Option<Action> {
+    let (pieces, squares) = (table[0].len(), table[0][0].len());
+
+    previous.and_then(|previous| {
+        let (piece, to) = piece_to_index(pieces, squares, previous);
+        table[team.next().index()][piece][to]
+    })
+}
+
+pub fn countermove(info: &SearchInfo, team: Team, previous: Option<Action>) -> Option<Action> {
+    refutation(&info.countermoves, team, previous)
+}
+
+pub fn counter_killer(info: &SearchInfo, team: Team, previous: Option<Action>) -> Option<Action> {
+    refutation(&info.counter_killers, team, previous)
+}
+
+// The new refutation takes the countermove slot, the old one moves down to the counter-killer
+pub fn update_countermove(info: &mut SearchInfo, team: Team, previous: Action, action: Action) {
+    let countermove = countermove_entry(&mut info.countermoves, team, previous);
+    if *countermove == Some(action) {
+        return;
+    }
+
+    let displaced = countermove.replace(action);
+    *countermove_entry(&mut info.counter_killers, team, previous) = displaced;
+}
+
+pub fn is_killer(info: &SearchInfo, ply: usize, act: Action) -> bool {
+    (0..MAX_KILLERS).any(|i| info.killers[i][ply] == Some(act))
+}
 pub const MAX_KILLERS: usize = 2;
+pub const KILLER_PLIES: usize = MAX_PLY;
 
 pub fn get_history<T: BitInt, const N: usize>(
     board: &mut Board<T, N>, 
@@ -74,21 +181,17 @@ pub fn get_history<T: BitInt, const N: usize>(
     two_ply: Option<Action>,
     noisy: bool
 ) -> i32 {
-    let to = act.to as usize;
-    let from = act.from as usize;
-    let piece = act.piece as usize;
-
     let team = board.state.moving_team;
 
     if noisy {
-        info.capture_history[team.index()][from][to]
+        history_value(&info.capture_history, team, act)
     } else {
-        let mut history = info.history[team.index()][from][to];
+        let mut history = history_value(&info.history, team, act);
         if let Some(previous) = previous {
-            history += info.conthist[team.next().index()][previous.piece as usize][previous.to as usize][team.index()][piece][to] / 2;
+            history += conthist_value(&info.conthist, team.next(), previous, team, act) / 2;
         }
         if let Some(previous) = two_ply {
-            history += info.conthist[team.index()][previous.piece as usize][previous.to as usize][team.index()][piece][to] / 2;
+            history += conthist_value(&info.conthist, team, previous, team, act) / 2;
         }
 
         history
@@ -110,8 +213,23 @@ pub fn score<T: BitInt, const N: usize>(
         }
     }
     
-    if is_noisy(board, act) {
-        return HIGH_PRIORITY + mvv_lva(board, act) + get_history(board, info, act, previous, two_ply, true);
+    // Underpromotions almost never matter, so they go below every quiet
+    if is_underpromotion(act) {
+        return -HIGH_PRIORITY + mvv_lva(board, act);
+    }
+
+    if is_noisy(board, info, act) {
+        let losing = promotion_piece(act).is_none()
+            && BitBoard::index(act.to).and(board.state.opposite_team()).is_set()
+            && see(board, info, act) < 0;
+        let tier = if losing { LOSING_CAPTURE_PRIORITY } else { HIGH_PRIORITY };
+
+        return tier + mvv_lva(board, act) + get_history(board, info, act, previous, two_ply, true);
+    }
+
+    if gives_check(board, info, act) {
+        let team = board.state.moving_team;
+        return QUIET_CHECK_PRIORITY + history_value(&info.check_history, team, act);
     }
 
     let mut score = get_history(board, info, act, previous, two_ply, false);
@@ -123,23 +241,34 @@ pub fn score<T: BitInt, const N: usize>(
         }
     }
 
+    if info.threats[ply].is_some_and(|threat| threat.to == act.from) {
+        score += THREAT_ESCAPE_BONUS;
+    }
+
+    let team = board.state.moving_team;
+    if countermove(info, team, previous) == Some(act) {
+        score += COUNTERMOVE_BONUS;
+    } else if counter_killer(info, team, previous) == Some(act) {
+        score += COUNTER_KILLER_BONUS;
+    }
+
     score
 }
 
+pub fn is_quiet_check_score(score: i32) -> bool {
+    (QUIET_CHECK_PRIORITY + MIN_HISTORY..HIGH_PRIORITY).contains(&score)
+}
+
 pub fn qs_score<T: BitInt, const N: usize>(
     board: &mut Board<T, N>, 
     info: &mut SearchInfo,
     act: Action
 ) -> i32 {
     let mut score = 0;
-    let to = act.to as usize;
-    let from = act.from as usize;
-    let piece = act.piece as usize;
-
     let team = board.state.moving_team;
 
     score += mvv_lva(board, act);
-    score += info.capture_history[team.index()][from][to];
+    score += history_value(&info.capture_history, team, act);
 
     score
 }
@@ -163,17 +292,47 @@ pub fn sort_actions<T: BitInt, const N: usize>(
     scored
 }
 
+// Captures that don't lose material (and promotions) come first, each tier in MVV-LVA order,
+// so once a losing capture comes up everything after it loses too
 pub fn sort_qs_actions<T: BitInt, const N: usize>(
     board: &mut Board<T, N>, 
     info: &mut SearchInfo,
     actions: Vec<Action>
-) -> Vec<ScoredAction> {
+) -> Vec<ScoredCapture> {
     let mut scored = vec![];
     for act in actions {
-        scored.push(ScoredAction(act, mvv_lva(board, act)))
+        let see_value = see(board, info, act);
+        let good = see_value >= 0 || promotion_piece(act).is_some();
+        scored.push(ScoredCapture(act, (good as i32) * GOOD_CAPTURE_TIER + mvv_lva(board, act), see_value))
     }
 
     scored.sort_by(|a, b| b.1.cmp(&a.1));
 
     scored
-}
\ No newline at end of file
+}
+
+// Killers two plies deep belong to sibling subtrees, which are only loosely related
+pub fn clear_child_killers(info: &mut SearchInfo, ply: usize) {
+    if ply + 1 < KILLER_PLIES {
+        for slot in info.killers.iter_mut() {
+            slot[ply + 1] = None;
+        }
+    }
+}
+
+// Keeps killers from the previous search lined up with the new root, or drops
+// them entirely if the position isn't a continuation of the last one
+pub fn shift_killers(info: &mut SearchInfo, root_ply: usize) {
+    let advanced = root_ply.wrapping_sub(info.killer_root);
+
+    for slot in info.killers.iter_mut() {
+        if advanced < KILLER_PLIES {
+            slot.drain(..advanced);
+            slot.resize(KILLER_PLIES, None);
+        } else {
+            slot.fill(None);
+        }
+    }
+
+    info.killer_root = root_ply;
+}
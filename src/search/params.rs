@@ -0,0 +1,127 @@
+// Search constants SPSA is allowed to move, settable over UCI by name
+#[derive(Clone, Debug)]
+pub struct SearchParams {
+    pub rfp_max_depth: i32,
+    pub rfp_margin: i32,
+    pub rfp_improving: i32,
+    // Share of the classical eval mixed into NNUE in unbalanced or bare positions, out of 1024
+    pub eval_blend: i32,
+    // Share of every history score kept from one iteration to the next, out of 1024
+    pub history_decay: i32,
+    // How far material and PSQT alone must be outside the window for the classical eval to stop there
+    pub lazy_eval_margin: i32,
+    // [piece] 256ths of a ply added to a late move's reduction, and centipawns added to the quiet
+    // futility margin, by the piece that moves (pawn to king; any further variant pieces get none)
+    pub lmr_piece: [i32; 6],
+    pub futility_piece: [i32; 6],
+    // [improving][depth], rebuilt whenever the values above change
+    pub rfp_margins: [Vec<i32>; 2]
+}
+
+pub struct Tunable {
+    pub name: &'static str,
+    pub default: i32,
+    pub min: i32,
+    pub max: i32,
+    // SPSA's final step size (c_end)
+    pub step: i32
+}
+
+pub const TUNABLES: [Tunable; 18] = [
+    Tunable { name: "RfpMaxDepth", default: 5, min: 1, max: 12, step: 1 },
+    Tunable { name: "RfpMargin", default: 100, min: 30, max: 200, step: 8 },
+    Tunable { name: "RfpImproving", default: 40, min: 0, max: 150, step: 8 },
+    Tunable { name: "EvalBlend", default: 256, min: 0, max: 1024, step: 32 },
+    Tunable { name: "HistoryDecay", default: 960, min: 768, max: 1024, step: 16 },
+    Tunable { name: "LazyEvalMargin", default: 400, min: 150, max: 2000, step: 32 },
+    Tunable { name: "LmrPawn", default: 0, min: -256, max: 256, step: 16 },
+    Tunable { name: "LmrKnight", default: 0, min: -256, max: 256, step: 16 },
+    Tunable { name: "LmrBishop", default: 0, min: -256, max: 256, step: 16 },
+    Tunable { name: "LmrRook", default: 0, min: -256, max: 256, step: 16 },
+    Tunable { name: "LmrQueen", default: 0, min: -256, max: 256, step: 16 },
+    Tunable { name: "LmrKing", default: 0, min: -256, max: 256, step: 16 },
+    Tunable { name: "FutilityPawn", default: 0, min: -150, max: 150, step: 10 },
+    Tunable { name: "FutilityKnight", default: 0, min: -150, max: 150, step: 10 },
+    Tunable { name: "FutilityBishop", default: 0, min: -150, max: 150, step: 10 },
+    Tunable { name: "FutilityRook", default: 0, min: -150, max: 150, step: 10 },
+    Tunable { name: "FutilityQueen", default: 0, min: -150, max: 150, step: 10 },
+    Tunable { name: "FutilityKing", default: 0, min: -150, max: 150, step: 10 }
+];
+
+fn param_mut<'a>(params: &'a mut SearchParams, name: &str) -> Option<&'a mut i32> {
+    match name {
+        "RfpMaxDepth" => Some(&mut params.rfp_max_depth),
+        "RfpMargin" => Some(&mut params.rfp_margin),
+        "RfpImproving" => Some(&mut params.rfp_improving),
+        "EvalBlend" => Some(&mut params.eval_blend),
+        "HistoryDecay" => Some(&mut params.history_decay),
+        "LazyEvalMargin" => Some(&mut params.lazy_eval_margin),
+        "LmrPawn" => Some(&mut params.lmr_piece[0]),
+        "LmrKnight" => Some(&mut params.lmr_piece[1]),
+        "LmrBishop" => Some(&mut params.lmr_piece[2]),
+        "LmrRook" => Some(&mut params.lmr_piece[3]),
+        "LmrQueen" => Some(&mut params.lmr_piece[4]),
+        "LmrKing" => Some(&mut params.lmr_piece[5]),
+        "FutilityPawn" => Some(&mut params.futility_piece[0]),
+        "FutilityKnight" => Some(&mut params.futility_piece[1]),
+        "FutilityBishop" => Some(&mut params.futility_piece[2]),
+        "FutilityRook" => Some(&mut params.futility_piece[3]),
+        "FutilityQueen" => Some(&mut params.futility_piece[4]),
+        "FutilityKing" => Some(&mut params.futility_piece[5]),
+        _ => None
+    }
+}
+
+fn build_rfp_margins(params: &mut SearchParams) {
+    let depths = params.rfp_max_depth.max(0) as usize + 1;
+
+    for improving in 0..2 {
+        params.rfp_margins[improving] = (0..depths)
+            .map(|depth| params.rfp_margin * depth as i32 - params.rfp_improving * improving as i32)
+            .collect();
+    }
+}
+
+pub fn create_search_params() -> SearchParams {
+    let mut params = SearchParams {
+        rfp_max_depth: 0,
+        rfp_margin: 0,
+        rfp_improving: 0,
+        eval_blend: 0,
+        history_decay: 0,
+        lazy_eval_margin: 0,
+        lmr_piece: [ 0; 6 ],
+        futility_piece: [ 0; 6 ],
+        rfp_margins: [ vec![], vec![] ]
+    };
+
+    for tunable in &TUNABLES {
+        *param_mut(&mut params, tunable.name).expect("Tunables have a field") = tunable.default;
+    }
+
+    build_rfp_margins(&mut params);
+    params
+}
+
+// Returns whether `name` is a tunable at all; values are clamped to its range
+pub fn set_param(params: &mut SearchParams, name: &str, value: i32) -> bool {
+    let tunable = match TUNABLES.iter().find(|tunable| tunable.name.eq_ignore_ascii_case(name)) {
+        Some(tunable) => tunable,
+        None => return false
+    };
+
+    *param_mut(params, tunable.name).expect("Tunables have a field") = value.clamp(tunable.min, tunable.max);
+    build_rfp_margins(params);
+
+    true
+}
+
+// OpenBench's SPSA input format: name, type, value, min, max, c_end, r_end
+pub fn spsa_inputs() -> Vec<String> {
+    TUNABLES.iter()
+        .map(|tunable| format!(
+            "{}, int, {}, {}, {}, {}, 0.002",
+            tunable.name, tunable.default, tunable.min, tunable.max, tunable.step
+        ))
+        .collect()
+}
@@ -0,0 +1,33 @@
+use chessing::{bitboard::{BitBoard, BitInt}, game::{action::Action, Board}};
+
+use super::{in_check, legal_actions, prepare_probe, quiescence, see::{hanging_squares, see}, SearchInfo, MAX, MIN};
+
+// The engine's definition of a quiet position, shared by datagen and any outside tooling:
+// not in check, and neither side wins material with a capture according to SEE
+pub fn is_quiet<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo) -> bool {
+    if in_check(board) {
+        return false;
+    }
+
+    let winning_capture = legal_actions(board).into_iter().any(|action| {
+        BitBoard::index(action.to).and(board.state.opposite_team()).is_set() && see(board, info, action) > 0
+    });
+
+    !winning_capture && hanging_squares(board, info).is_empty()
+}
+
+// The position qsearch settles on, reached by playing its PV on a copy of `board`; the
+// line is returned with it so callers can report it
+pub fn resolve_to_quiet<T: BitInt, const N: usize>(board: &Board<T, N>, info: &mut SearchInfo) -> (Board<T, N>, Vec<Action>) {
+    let mut resolved = board.clone();
+
+    prepare_probe(info);
+    quiescence(&mut resolved, info, 0, MIN, MAX);
+
+    let pv = info.pv_table[0].clone();
+    for &action in &pv {
+        resolved.play(action);
+    }
+
+    (resolved, pv)
+}
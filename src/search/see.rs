@@ -0,0 +1,295 @@
+use chessing::{bitboard::{BitBoard, BitInt}, game::{action::Action, Board, Team}};
+
+use crate::eval::MATERIAL;
+
+use super::{legal_actions, SearchInfo};
+
+// The king only ever captures last, neither `exchange` nor legal move generation let it capture
+// into a defended square
+const KING_VALUE: i32 = 20_000;
+
+fn piece_value(piece: usize) -> i32 {
+    match MATERIAL.get(piece) {
+        Some(0) => KING_VALUE,
+        Some(&value) => value,
+        None => MATERIAL[1]
+    }
+}
+
+fn captured_value<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> i32 {
+    if !BitBoard::index(action.to).and(board.state.opposite_team()).is_set() {
+        return 0;
+    }
+
+    board.piece_at(action.to).map_or(0, |piece| piece_value(piece as usize))
+}
+
+fn least_valuable_capture<T: BitInt, const N: usize>(board: &mut Board<T, N>, sq: usize) -> Option<Action> {
+    legal_actions(board)
+        .into_iter()
+        .filter(|action| action.to as usize == sq && BitBoard::index(action.to).and(board.state.opposite_team()).is_set())
+        .min_by_key(|action| piece_value(action.piece as usize))
+}
+
+// Static exchange evaluation by playing out legal recaptures on the target square, for variants
+// whose pieces the attack map doesn't know; pins and checks are respected at the cost of full
+// move generation per step
+fn played_see<T: BitInt, const N: usize>(board: &mut Board<T, N>, action: Action) -> i32 {
+    let mut gains = vec![ captured_value(board, action) ];
+    let mut on_square = piece_value(action.piece as usize);
+    let mut states = vec![ board.play(action) ];
+
+    while let Some(capture) = least_valuable_capture(board, action.to as usize) {
+        let previous = *gains.last().expect("Gains start non-empty");
+        gains.push(on_square - previous);
+
+        on_square = piece_value(capture.piece as usize);
+        states.push(board.play(capture));
+    }
+
+    while let Some(state) = states.pop() {
+        board.restore(state);
+    }
+
+    // Either side may stop capturing when continuing loses material
+    while gains.len() > 1 {
+        let last = gains.pop().expect("Gains are non-empty");
+        let previous = gains.last_mut().expect("Gains are non-empty");
+        *previous = -(-*previous).max(last);
+    }
+
+    gains[0]
+}
+
+fn played_hanging_squares<T: BitInt, const N: usize>(board: &mut Board<T, N>) -> Vec<usize> {
+    let state = board.play_null();
+    if !board.game.rules.is_legal(board) {
+        board.restore(state);
+        return vec![];
+    }
+
+    let mut hanging = vec![];
+    for action in legal_actions(board) {
+        let to = action.to as usize;
+        if !hanging.contains(&to) && captured_value(board, action) > 0 && played_see(board, action) > 0 {
+            hanging.push(to);
+        }
+    }
+
+    board.restore(state);
+    hanging
+}
+
+// Chess piece indices and movement, as in `MATERIAL`; generic variants never get this far
+const PAWN: usize = 0;
+const KNIGHT: usize = 1;
+const BISHOP: usize = 2;
+const ROOK: usize = 3;
+const QUEEN: usize = 4;
+const KING: usize = 5;
+
+const KNIGHT_STEPS: [(i32, i32); 8] = [ (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2) ];
+const KING_STEPS: [(i32, i32); 8] = [ (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1) ];
+const ROOK_RAYS: [(i32, i32); 4] = [ (1, 0), (0, 1), (-1, 0), (0, -1) ];
+const BISHOP_RAYS: [(i32, i32); 4] = [ (1, 1), (-1, 1), (-1, -1), (1, -1) ];
+
+// The board as (piece, is white) per square, read off the bitboards once and then changed in
+// place as an exchange plays out, so nothing is ever generated or played on the real board
+#[derive(Clone)]
+struct Squares {
+    cols: i32,
+    rows: i32,
+    occupant: Vec<Option<(usize, bool)>>
+}
+
+fn read_squares<T: BitInt, const N: usize>(board: &Board<T, N>) -> Squares {
+    let (cols, rows) = (board.game.bounds.cols as i32, board.game.bounds.rows as i32);
+    let mut occupant = vec![ None; (cols * rows) as usize ];
+
+    for (piece, pieces) in board.state.pieces.iter().enumerate() {
+        for sq in pieces.and(board.state.white).iter() { occupant[sq as usize] = Some((piece, true)); }
+        for sq in pieces.and(board.state.black).iter() { occupant[sq as usize] = Some((piece, false)); }
+    }
+
+    Squares { cols, rows, occupant }
+}
+
+impl Squares {
+    fn step(&self, sq: usize, (file_step, rank_step): (i32, i32)) -> Option<usize> {
+        let (file, rank) = (sq as i32 % self.cols + file_step, sq as i32 / self.cols + rank_step);
+        let inside = (0..self.cols).contains(&file) && (0..self.rows).contains(&rank);
+        inside.then_some((rank * self.cols + file) as usize)
+    }
+
+    fn first_along(&self, sq: usize, ray: (i32, i32)) -> Option<(usize, usize, bool)> {
+        let mut at = sq;
+        while let Some(next) = self.step(at, ray) {
+            if let Some((piece, white)) = self.occupant[next] {
+                return Some((next, piece, white));
+            }
+            at = next;
+        }
+
+        None
+    }
+
+    // (square, piece) of every piece of one side attacking `sq`, sliders seen through whatever
+    // has already left the board
+    fn attackers(&self, sq: usize, white: bool) -> Vec<(usize, usize)> {
+        let mut attackers = vec![];
+        let mut leaper = |steps: &[(i32, i32)], wanted: usize| {
+            for &step in steps {
+                if let Some(from) = self.step(sq, step) {
+                    if self.occupant[from] == Some((wanted, white)) {
+                        attackers.push((from, wanted));
+                    }
+                }
+            }
+        };
+
+        // A white pawn takes towards higher ranks, so it stands a rank below what it attacks
+        let pawn_rank = if white { -1 } else { 1 };
+        leaper(&[ (-1, pawn_rank), (1, pawn_rank) ], PAWN);
+        leaper(&KNIGHT_STEPS, KNIGHT);
+        leaper(&KING_STEPS, KING);
+
+        for (rays, slider) in [ (ROOK_RAYS, ROOK), (BISHOP_RAYS, BISHOP) ] {
+            for ray in rays {
+                if let Some((from, piece, side)) = self.first_along(sq, ray) {
+                    if side == white && (piece == slider || piece == QUEEN) {
+                        attackers.push((from, piece));
+                    }
+                }
+            }
+        }
+
+        attackers
+    }
+
+    fn least_valuable_attacker(&self, sq: usize, white: bool) -> Option<(usize, usize)> {
+        self.attackers(sq, white).into_iter().min_by_key(|&(_, piece)| piece_value(piece))
+    }
+
+    // Material `white` ends up with from starting the exchange on `sq` with the piece on `from`,
+    // each side free to stop once capturing on loses more
+    fn exchange(&mut self, sq: usize, from: usize, white: bool) -> i32 {
+        let mut gains = vec![ self.occupant[sq].map_or(0, |(piece, _)| piece_value(piece)) ];
+        let mut on_square = self.occupant[from].map_or(0, |(piece, _)| piece_value(piece));
+        self.occupant[sq] = self.occupant[from];
+        self.occupant[from] = None;
+        let mut side = !white;
+
+        while let Some((from, piece)) = self.least_valuable_attacker(sq, side) {
+            // The king can't take into a square the other side still covers
+            if piece == KING && !self.attackers(sq, !side).is_empty() {
+                break;
+            }
+
+            let previous = *gains.last().expect("Gains start non-empty");
+            gains.push(on_square - previous);
+
+            on_square = piece_value(piece);
+            self.occupant[sq] = Some((piece, side));
+            self.occupant[from] = None;
+            side = !side;
+        }
+
+        while gains.len() > 1 {
+            let last = gains.pop().expect("Gains are non-empty");
+            let previous = gains.last_mut().expect("Gains are non-empty");
+            *previous = -(-*previous).max(last);
+        }
+
+        gains[0]
+    }
+}
+
+// Whether a move checks the opposing king, directly or by uncovering a slider, read off the attack
+// map; `None` for moves that change more than two squares (castling, en passant), which it can't
+// see, and for generic variants, whose pieces it doesn't know
+pub fn simple_gives_check<T: BitInt, const N: usize>(board: &Board<T, N>, info: &SearchInfo, action: Action) -> Option<bool> {
+    if info.generic {
+        return None;
+    }
+
+    let mut squares = read_squares(board);
+    let white = board.state.moving_team == Team::White;
+    let (from, to) = (action.from as usize, action.to as usize);
+    let file_distance = (from as i32 % squares.cols - to as i32 % squares.cols).abs();
+
+    match squares.occupant[to] {
+        Some((_, side)) if side == white => return None,
+        None if action.piece as usize == PAWN && file_distance != 0 => return None,
+        _ if action.piece as usize == KING && file_distance > 1 => return None,
+        _ => {}
+    }
+
+    squares.occupant[to] = squares.occupant[from];
+    squares.occupant[from] = None;
+
+    let king = (0..squares.occupant.len()).find(|&sq| squares.occupant[sq] == Some((KING, !white)))?;
+    Some(!squares.attackers(king, white).is_empty())
+}
+
+// Static exchange evaluation from the attackers of the target square alone, x-rays included as
+// pieces leave it; pins are ignored, and en passant counts as winning nothing
+pub fn see<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo, action: Action) -> i32 {
+    if info.generic {
+        return played_see(board, action);
+    }
+
+    let mut squares = read_squares(board);
+    let white = board.state.moving_team == Team::White;
+
+    squares.exchange(action.to as usize, action.from as usize, white)
+}
+
+// The opponent's most profitable capture if the side to move passed, `None` if nothing hangs
+pub fn biggest_threat<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo) -> Option<Action> {
+    let state = board.play_null();
+    if !board.game.rules.is_legal(board) {
+        board.restore(state);
+        return None;
+    }
+
+    let mut best: Option<(Action, i32)> = None;
+    for action in legal_actions(board) {
+        if captured_value(board, action) == 0 {
+            continue;
+        }
+
+        let gain = see(board, info, action);
+        if gain > 0 && best.map_or(true, |(_, best_gain)| gain > best_gain) {
+            best = Some((action, gain));
+        }
+    }
+
+    board.restore(state);
+    best.map(|(action, _)| action)
+}
+
+// Squares of the side to move's pieces that the opponent wins material by capturing; nothing
+// while in check, when the check is what has to be dealt with
+pub fn hanging_squares<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &SearchInfo) -> Vec<usize> {
+    if info.generic {
+        return played_hanging_squares(board);
+    }
+
+    let squares = read_squares(board);
+    let white = board.state.moving_team == Team::White;
+    let own = |sq: usize| squares.occupant[sq].filter(|&(_, side)| side == white);
+
+    let in_check = (0..squares.occupant.len())
+        .any(|sq| own(sq).is_some_and(|(piece, _)| piece == KING) && !squares.attackers(sq, !white).is_empty());
+    if in_check {
+        return vec![];
+    }
+
+    (0..squares.occupant.len())
+        .filter(|&sq| own(sq).is_some_and(|(piece, _)| piece != KING))
+        .filter(|&sq| match squares.least_valuable_attacker(sq, !white) {
+            Some((from, _)) => squares.clone().exchange(sq, from, !white) > 0,
+            None => false
+        })
+        .collect()
+}
@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+// Counters gathered while searching, kept for the whole game so rates settle
+#[derive(Clone)]
+pub struct SearchStats {
+    // [depth] null-window probes at PV nodes, and how many of them had to be re-searched
+    pub pvs_probes: Vec<u64>,
+    pub pvs_researches: Vec<u64>,
+    // Completed iterations of the last search only
+    pub iterations: Vec<Iteration>,
+    // (time, nodes) when recent iterations finished, see `smoothed_nps`
+    pub nps_samples: VecDeque<(u64, u64)>,
+    // Classical evals asked for with a window, and how many stopped early outside it
+    pub windowed_evals: u64,
+    pub lazy_evals: u64
+}
+
+#[derive(Clone)]
+pub struct Iteration {
+    pub depth: i32,
+    // Totals for the search when the iteration finished
+    pub nodes: u64,
+    pub time: u64
+}
+
+// Below this depth a PV node may skip the probe, if re-searches are this common
+pub const FULL_WINDOW_MAX_DEPTH: i32 = 2;
+pub const FULL_WINDOW_MIN_PROBES: u64 = 256;
+pub const FULL_WINDOW_RESEARCH_RATE: f64 = 0.5;
+
+pub fn create_search_stats() -> SearchStats {
+    SearchStats {
+        pvs_probes: vec![ 0; 128 ],
+        pvs_researches: vec![ 0; 128 ],
+        iterations: vec![],
+        nps_samples: VecDeque::new(),
+        windowed_evals: 0,
+        lazy_evals: 0
+    }
+}
+
+fn depth_index(stats: &SearchStats, depth: i32) -> usize {
+    (depth.max(0) as usize).min(stats.pvs_probes.len() - 1)
+}
+
+pub fn record_probe(stats: &mut SearchStats, depth: i32, researched: bool) {
+    let index = depth_index(stats, depth);
+    stats.pvs_probes[index] += 1;
+    if researched {
+        stats.pvs_researches[index] += 1;
+    }
+}
+
+pub fn research_rate(stats: &SearchStats, depth: i32) -> Option<f64> {
+    let index = depth_index(stats, depth);
+    let probes = stats.pvs_probes[index];
+
+    (probes > 0).then(|| stats.pvs_researches[index] as f64 / probes as f64)
+}
+
+// A probe that fails high costs a second search, which only pays off when most don't
+pub fn full_window_pays(stats: &SearchStats, depth: i32) -> bool {
+    depth <= FULL_WINDOW_MAX_DEPTH
+        && stats.pvs_probes[depth_index(stats, depth)] >= FULL_WINDOW_MIN_PROBES
+        && research_rate(stats, depth).is_some_and(|rate| rate > FULL_WINDOW_RESEARCH_RATE)
+}
+
+pub fn record_iteration(stats: &mut SearchStats, depth: i32, nodes: u64, time: u64) {
+    stats.iterations.push(Iteration { depth, nodes, time });
+}
+
+// Milliseconds `nps` is averaged over; dividing by a single short iteration swings wildly at low depths
+pub const NPS_WINDOW: u64 = 1000;
+
+// Keeps the latest sample at least a window old as the baseline, dropping the ones before it
+pub fn record_nps_sample(stats: &mut SearchStats, time: u64, nodes: u64) {
+    if stats.nps_samples.is_empty() {
+        stats.nps_samples.push_back((0, 0));
+    }
+    stats.nps_samples.push_back((time, nodes));
+
+    while stats.nps_samples.get(1).is_some_and(|&(sampled, _)| time - sampled >= NPS_WINDOW) {
+        stats.nps_samples.pop_front();
+    }
+}
+
+pub fn smoothed_nps(stats: &SearchStats, time: u64, nodes: u64) -> u64 {
+    let (since, since_nodes) = stats.nps_samples.front().copied().unwrap_or((0, 0));
+
+    match time.saturating_sub(since) {
+        0 => nodes * 1000 / time.max(1),
+        elapsed => nodes.saturating_sub(since_nodes) * 1000 / elapsed
+    }
+}
+
+pub fn stats_report(stats: &SearchStats) -> Vec<String> {
+    let mut lines: Vec<String> = (0..stats.pvs_probes.len() as i32)
+        .filter_map(|depth| research_rate(stats, depth).map(|rate| format!(
+            "info string stats depth {} pvs probes {} researches {} rate {:.3}{}",
+            depth, stats.pvs_probes[depth as usize], stats.pvs_researches[depth as usize], rate,
+            if full_window_pays(stats, depth) { " full-window" } else { "" }
+        )))
+        .collect();
+
+    // Effective branching factor: how many times more nodes each iteration took than the last
+    let mut previous: Option<&Iteration> = None;
+    let mut previous_nodes = 0;
+    for iteration in &stats.iterations {
+        let nodes = iteration.nodes - previous.map_or(0, |previous| previous.nodes);
+        let time = iteration.time - previous.map_or(0, |previous| previous.time);
+        let ebf = if previous_nodes > 0 { format!("{:.2}", nodes as f64 / previous_nodes as f64) } else { "-".to_string() };
+
+        lines.push(format!("info string iteration depth {} nodes {} time {} ebf {}", iteration.depth, nodes, time, ebf));
+        previous = Some(iteration);
+        previous_nodes = nodes;
+    }
+
+    if stats.windowed_evals > 0 {
+        lines.push(format!(
+            "info string stats lazy evals {} of {} rate {:.3}",
+            stats.lazy_evals, stats.windowed_evals, stats.lazy_evals as f64 / stats.windowed_evals as f64
+        ));
+    }
+
+    lines
+}
@@ -0,0 +1,127 @@
+use chessing::{bitboard::BitInt, game::{Board, Team}};
+
+use crate::eval::evaluate;
+
+use super::{in_check, limits::{is_bounded, Limits}, phase_pieces, prepare_probe, quiescence, see::hanging_squares, SearchInfo, MAX, MIN};
+
+// Quiescence disagreeing with the static eval by this much means captures are pending
+pub const TACTICAL_QS_SWING: i32 = 80;
+// The score moving this much since our last search means the opponent's move changed things
+pub const TACTICAL_ROOT_SWING: i32 = 60;
+// Dead-equal positions, where extra thought rarely changes the move
+pub const QUIET_SCORE: i32 = 30;
+// The first search of a game comes straight out of an instant book line, with nothing in the TT
+pub const FIRST_SEARCH_TIME_PERCENT: u64 = 150;
+// With a single legal move the search is only there to report a score and PV
+pub const FORCED_MOVE_DEPTH: i32 = 4;
+pub const FORCED_MOVE_TIME: u64 = 20;
+// Pieces besides royals and fillers (kings and pawns) left when the game counts as an endgame,
+// where depth finds the plan so the clock is spent more freely
+pub const ENDGAME_PIECES: u32 = 4;
+pub const ENDGAME_TIME_PERCENT: u64 = 140;
+// How far past the soft limit an endgame may go while its score is still climbing
+pub const ENDGAME_TREND_PERCENT: u64 = 160;
+
+// A clock is spread over this many moves unless `movestogo` says fewer are left
+pub const DEFAULT_MOVES_TO_GO: u64 = 40;
+// The soft limit when nothing in `go` gives one
+pub const DEFAULT_SOFT_TIME: u64 = 300;
+
+// (soft, hard, on the clock) before any of the adjustments below; u64::MAX is no limit, and a
+// hard limit of 0 means no clock for the side to move
+pub fn clock_times(limits: &Limits, team: Team) -> (u64, u64, bool) {
+    let (time, inc) = match team {
+        Team::White => (limits.wtime, limits.winc),
+        Team::Black => (limits.btime, limits.binc)
+    };
+
+    let mut soft_time = 0;
+    let mut hard_time = 0;
+    let on_clock = time.is_some();
+
+    if let Some(time) = time {
+        soft_time += time / limits.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).clamp(1, DEFAULT_MOVES_TO_GO);
+        hard_time += time / 9;
+    }
+
+    soft_time += inc.unwrap_or(0) / 4;
+
+    if let Some(time) = limits.movetime {
+        soft_time += time / 2;
+        hard_time += time;
+    }
+
+    if limits.infinite || (is_bounded(limits) && hard_time == 0) {
+        return (u64::MAX, u64::MAX, on_clock);
+    }
+
+    if soft_time == 0 {
+        soft_time = DEFAULT_SOFT_TIME;
+    }
+
+    (soft_time, hard_time, on_clock)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionClass {
+    Tactical,
+    Normal,
+    Quiet
+}
+
+pub fn classify_root<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &mut SearchInfo) -> PositionClass {
+    prepare_probe(info);
+
+    if in_check(board) || !hanging_squares(board, info).is_empty() {
+        return PositionClass::Tactical;
+    }
+
+    let static_eval = evaluate(board, info, 0);
+    let resolved = quiescence(board, info, 0, MIN, MAX);
+    let root_swing = info.last_search_score.map_or(0, |previous| (resolved - previous).abs());
+
+    if (resolved - static_eval).abs() >= TACTICAL_QS_SWING || root_swing >= TACTICAL_ROOT_SWING {
+        PositionClass::Tactical
+    } else if resolved.abs() <= QUIET_SCORE {
+        PositionClass::Quiet
+    } else {
+        PositionClass::Normal
+    }
+}
+
+pub fn scale_soft_time(soft_time: u64, class: PositionClass) -> u64 {
+    match class {
+        PositionClass::Tactical => soft_time * 3 / 2,
+        PositionClass::Normal => soft_time,
+        PositionClass::Quiet => soft_time * 3 / 4
+    }
+}
+
+pub fn is_endgame<T: BitInt, const N: usize>(board: &Board<T, N>, info: &SearchInfo) -> bool {
+    phase_pieces(board, info) <= ENDGAME_PIECES
+}
+
+pub fn scale_endgame_time(info: &SearchInfo, soft_time: u64) -> u64 {
+    if info.endgame {
+        soft_time * ENDGAME_TIME_PERCENT / 100
+    } else {
+        soft_time
+    }
+}
+
+// Extends the soft limit in endgames whose score rose over the last iteration
+pub fn endgame_soft_time(info: &SearchInfo, soft_time: u64, previous_score: Option<i32>) -> u64 {
+    if info.endgame && previous_score.is_some_and(|previous| info.score > previous) {
+        soft_time.saturating_mul(ENDGAME_TREND_PERCENT) / 100
+    } else {
+        soft_time
+    }
+}
+
+pub fn book_exit_time(info: &SearchInfo, soft_time: u64) -> u64 {
+    if info.game_searches == 0 {
+        soft_time * FIRST_SEARCH_TIME_PERCENT / 100
+    } else {
+        soft_time
+    }
+}
@@ -0,0 +1,255 @@
+use std::{ops::Range, sync::atomic::{AtomicU64, Ordering}};
+
+use chessing::game::action::Action;
+
+use super::{Bounds, SearchInfo, TtEntry, MATE_THRESHOLD};
+
+// Which entry a store overwrites, picked per hash size with `bench tt`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Replacement {
+    // The single slot a hash maps to, whatever is in it
+    Always,
+    // The single slot, unless it holds a deeper search of another position
+    Depth,
+    // The shallowest of a bucket of slots, counting searches since an entry was written against it
+    Aged
+}
+
+pub const REPLACEMENTS: [(&str, Replacement); 3] = [
+    ("always", Replacement::Always),
+    ("depth", Replacement::Depth),
+    ("aged", Replacement::Aged)
+];
+
+pub fn find_replacement(name: &str) -> Option<Replacement> {
+    REPLACEMENTS.iter()
+        .find(|(policy, _)| policy.eq_ignore_ascii_case(name))
+        .map(|&(_, replacement)| replacement)
+}
+
+// Megabytes, the `Hash` option's default
+pub const DEFAULT_HASH: usize = 16;
+
+// An entry packed into words the search threads can share without locking. `key` is the hash
+// xored with the other two, so a slot torn by two threads writing at once no longer matches
+// either position and is just a miss
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+    action: AtomicU64
+}
+
+// Shared by every search thread, see `threads`
+pub struct Tt {
+    slots: Vec<Slot>
+}
+
+pub fn create_tt(entries: usize) -> Tt {
+    Tt { slots: (0..entries.max(1)).map(|_| Slot::default()).collect() }
+}
+
+pub fn tt_entries(megabytes: usize) -> usize {
+    megabytes * 1024 * 1024 / std::mem::size_of::<Slot>()
+}
+
+impl Tt {
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<TtEntry> {
+        let slot = self.slots.get(index)?;
+        let (key, data, action) = (slot.key.load(Ordering::Relaxed), slot.data.load(Ordering::Relaxed), slot.action.load(Ordering::Relaxed));
+
+        // Nothing has been written here, every entry has a non-zero depth
+        if data == 0 {
+            return None;
+        }
+
+        Some(unpack_entry(key ^ data ^ action, data, action))
+    }
+
+    pub fn set(&self, index: usize, entry: &TtEntry) {
+        if let Some(slot) = self.slots.get(index) {
+            let (data, action) = (pack_data(entry), pack_action(entry.best_move));
+
+            slot.key.store(entry.hash ^ data ^ action, Ordering::Relaxed);
+            slot.data.store(data, Ordering::Relaxed);
+            slot.action.store(action, Ordering::Relaxed);
+        }
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+            slot.action.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+fn bound_bits(bounds: Bounds) -> u64 {
+    match bounds {
+        Bounds::Exact => 0,
+        Bounds::Lower => 1,
+        Bounds::Upper => 2
+    }
+}
+
+// Score in the low 32 bits, then depth, bounds, the PV flag and generation
+fn pack_data(entry: &TtEntry) -> u64 {
+    (entry.score as u32 as u64)
+        | (entry.depth.clamp(1, 255) as u64) << 32
+        | bound_bits(entry.bounds) << 40
+        | (entry.was_pv as u64) << 42
+        | (entry.generation as u64) << 43
+}
+
+// The top bit marks a move being there at all
+fn pack_action(action: Option<Action>) -> u64 {
+    match action {
+        Some(action) => 1 << 63
+            | (action.from as u64 & 0xFFFF) << 40
+            | (action.to as u64 & 0xFFFF) << 24
+            | (action.piece as u64 & 0xFF) << 16
+            | (action.info as u64 & 0xFFFF),
+        None => 0
+    }
+}
+
+fn unpack_entry(hash: u64, data: u64, action: u64) -> TtEntry {
+    TtEntry {
+        hash,
+        best_move: (action >> 63 == 1).then(|| Action {
+            from: ((action >> 40) & 0xFFFF) as _,
+            to: ((action >> 24) & 0xFFFF) as _,
+            piece: ((action >> 16) & 0xFF) as _,
+            info: (action & 0xFFFF) as _
+        }),
+        score: data as u32 as i32,
+        depth: ((data >> 32) & 0xFF) as i32,
+        bounds: match (data >> 40) & 0b11 {
+            0 => Bounds::Exact,
+            1 => Bounds::Lower,
+            _ => Bounds::Upper
+        },
+        was_pv: (data >> 42) & 1 == 1,
+        generation: ((data >> 43) & 0xFF) as u8
+    }
+}
+
+// Permille of the table written this search, sampled from the first thousand slots (UCI `hashfull`)
+pub fn hashfull(info: &SearchInfo) -> usize {
+    let sampled = info.tt.len().min(1000);
+    let used = (0..sampled)
+        .filter_map(|slot| info.tt.get(slot))
+        .filter(|entry| entry.generation == info.tt_generation)
+        .count();
+
+    used * 1000 / sampled.max(1)
+}
+
+// Mate scores count plies from the root, but an entry can be found again at any ply, so they're
+// stored counting from the entry's own node instead
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score + ply as i32
+    } else if score <= -MATE_THRESHOLD {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score - ply as i32
+    } else if score <= -MATE_THRESHOLD {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// Slots per bucket under `Aged`, neighbours in the table so a probe stays in one cache line or two
+pub const TT_BUCKET: usize = 4;
+// Plies of depth an entry is worth less for every search since it was written
+pub const AGE_PENALTY: i32 = 4;
+
+fn bucket(info: &SearchInfo, hash: u64) -> Range<usize> {
+    let index = (hash % info.tt.len() as u64) as usize;
+
+    match info.tt_replacement {
+        Replacement::Aged => {
+            let start = index - index % TT_BUCKET;
+            start..(start + TT_BUCKET).min(info.tt.len())
+        }
+        Replacement::Always | Replacement::Depth => index..index + 1
+    }
+}
+
+pub fn tt_probe(info: &SearchInfo, hash: u64) -> Option<TtEntry> {
+    bucket(info, hash)
+        .filter_map(|slot| info.tt.get(slot))
+        .find(|entry| entry.hash == hash)
+}
+
+fn worth(info: &SearchInfo, entry: &TtEntry) -> i32 {
+    let age = info.tt_generation.wrapping_sub(entry.generation) as i32;
+    entry.depth - age * AGE_PENALTY
+}
+
+// Plies deeper a non-exact entry has to be to push out an exact one from the same search
+pub const PV_DEPTH_MARGIN: i32 = 2;
+
+// Exact entries are the PV, which otherwise churns as the rest of the tree lands on its slots
+fn protected(info: &SearchInfo, old: &TtEntry, entry: &TtEntry) -> bool {
+    old.hash != entry.hash
+        && matches!(old.bounds, Bounds::Exact)
+        && !matches!(entry.bounds, Bounds::Exact)
+        && old.generation == info.tt_generation
+        && entry.depth < old.depth + PV_DEPTH_MARGIN
+}
+
+pub fn tt_store(info: &SearchInfo, mut entry: TtEntry) {
+    let slots = bucket(info, entry.hash);
+
+    let slot = match info.tt_replacement {
+        Replacement::Always => slots.start,
+        Replacement::Depth => {
+            match info.tt.get(slots.start) {
+                Some(old) if old.hash != entry.hash && old.depth > entry.depth => return,
+                Some(old) if protected(info, &old, &entry) => return,
+                _ => slots.start
+            }
+        }
+        Replacement::Aged => {
+            let entries: Vec<(usize, Option<TtEntry>)> = slots.map(|slot| (slot, info.tt.get(slot))).collect();
+
+            let same = entries.iter().find(|(_, old)| old.as_ref().is_some_and(|old| old.hash == entry.hash));
+            let empty = entries.iter().find(|(_, old)| old.is_none());
+            let weakest = entries.iter()
+                .filter(|(_, old)| !old.as_ref().is_some_and(|old| protected(info, old, &entry)))
+                .min_by_key(|(_, old)| old.as_ref().map_or(i32::MIN, |old| worth(info, old)));
+
+            match same.or(empty).or(weakest) {
+                Some(&(slot, _)) => slot,
+                None => return
+            }
+        }
+    };
+
+    // A fail low finds no best move, but the one stored for the position is still the best guess
+    if entry.best_move.is_none() {
+        if let Some(old) = info.tt.get(slot).filter(|old| old.hash == entry.hash) {
+            entry.best_move = old.best_move;
+        }
+    }
+
+    info.tt.set(slot, &entry);
+}
@@ -0,0 +1,145 @@
+use std::fs;
+
+use chessing::{bitboard::BitInt, game::Board};
+
+use crate::{san::parse_uci, search::{tt::{tt_probe, tt_store}, Bounds, RootMove, SearchInfo, TtEntry}};
+
+pub const SESSION_VERSION: u32 = 1;
+
+// Everything needed to pick an analysis back up after a restart; the position is kept as
+// the `position` command had it, so loading replays it exactly
+pub struct Session {
+    pub fen: Option<String>,
+    pub moves: Vec<String>,
+    // score, previous score, nodes, PV
+    pub root_moves: Vec<(i32, i32, u64, Vec<String>)>,
+    // TT slot, hash, score, depth, bound; the best move is recovered from the root PVs
+    pub tt: Vec<(usize, u64, i32, i32, Bounds)>
+}
+
+fn bound_name(bound: Bounds) -> &'static str {
+    match bound {
+        Bounds::Exact => "exact",
+        Bounds::Lower => "lower",
+        Bounds::Upper => "upper"
+    }
+}
+
+fn parse_bound(bound: &str) -> Option<Bounds> {
+    match bound {
+        "exact" => Some(Bounds::Exact),
+        "lower" => Some(Bounds::Lower),
+        "upper" => Some(Bounds::Upper),
+        _ => None
+    }
+}
+
+pub fn save_session<T: BitInt, const N: usize>(
+    path: &str,
+    board: &mut Board<T, N>,
+    info: &SearchInfo,
+    fen: Option<&str>,
+    moves: &[String],
+    include_tt: bool
+) -> Result<(), String> {
+    let mut lines = vec![
+        format!("artifact-session {}", SESSION_VERSION),
+        format!("position {}", fen.unwrap_or("startpos")),
+        format!("moves {}", moves.join(" "))
+    ];
+
+    for root_move in &info.root_moves {
+        let pv: Vec<String> = root_move.pv.iter().map(|&action| board.display_uci_action(action)).collect();
+        lines.push(format!("rootmove {} {} {} {}", root_move.score, root_move.previous_score, root_move.nodes, pv.join(" ")));
+    }
+
+    if include_tt {
+        for index in 0..info.tt.len() {
+            if let Some(entry) = info.tt.get(index) {
+                lines.push(format!("tt {} {} {} {} {}", index, entry.hash, entry.score, entry.depth, bound_name(entry.bounds)));
+            }
+        }
+    }
+
+    lines.push(String::new());
+    fs::write(path, lines.join("\n")).map_err(|error| format!("failed to write {}: {}", path, error))
+}
+
+pub fn load_session(path: &str) -> Result<Session, String> {
+    let contents = fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let mut session = Session { fen: None, moves: vec![], root_moves: vec![], tt: vec![] };
+
+    for (number, line) in contents.lines().enumerate() {
+        let bad_line = || format!("{}:{}: malformed line", path, number + 1);
+        let args: Vec<&str> = line.split_whitespace().collect();
+
+        match args.as_slice() {
+            ["artifact-session", version] => {
+                if version.parse() != Ok(SESSION_VERSION) {
+                    return Err(format!("{} is session version {}, expected {}", path, version, SESSION_VERSION));
+                }
+            }
+            ["position", "startpos"] => session.fen = None,
+            ["position", fen @ ..] => session.fen = Some(fen.join(" ")),
+            ["moves", moves @ ..] => session.moves = moves.iter().map(|action| action.to_string()).collect(),
+            ["rootmove", score, previous, nodes, pv @ ..] => {
+                let parsed = (score.parse(), previous.parse(), nodes.parse());
+                let (Ok(score), Ok(previous), Ok(nodes)) = parsed else { return Err(bad_line()) };
+
+                session.root_moves.push((score, previous, nodes, pv.iter().map(|action| action.to_string()).collect()));
+            }
+            ["tt", index, hash, score, depth, bound] => {
+                let parsed = (index.parse(), hash.parse(), score.parse(), depth.parse(), parse_bound(bound));
+                let (Ok(index), Ok(hash), Ok(score), Ok(depth), Some(bound)) = parsed else { return Err(bad_line()) };
+
+                session.tt.push((index, hash, score, depth, bound));
+            }
+            [] => {}
+            _ => return Err(bad_line())
+        }
+    }
+
+    Ok(session)
+}
+
+// Call with the session's position already set up; restores the TT before the root moves,
+// as walking the PVs is what puts best moves back into the loaded entries
+pub fn apply_session<T: BitInt, const N: usize>(board: &mut Board<T, N>, info: &mut SearchInfo, session: &Session) {
+    for &(index, hash, score, depth, bounds) in &session.tt {
+        info.tt.set(index, &TtEntry { hash, best_move: None, score, depth, bounds, was_pv: false, generation: info.tt_generation });
+    }
+
+    let mut root_moves = vec![];
+    for (score, previous_score, nodes, pv) in &session.root_moves {
+        let mut actions = vec![];
+        let mut states = vec![];
+
+        for uci_move in pv {
+            let action = match parse_uci(board, uci_move) {
+                Some(action) => action,
+                None => break
+            };
+
+            let hash = board.game.rules.hash(board, &info.zobrist);
+            if let Some(entry) = tt_probe(info, hash).filter(|entry| entry.best_move.is_none()) {
+                tt_store(info, TtEntry { best_move: Some(action), ..entry });
+            }
+
+            actions.push(action);
+            states.push(board.play(action));
+        }
+
+        while let Some(state) = states.pop() {
+            board.restore(state);
+        }
+
+        if let Some(&action) = actions.first() {
+            root_moves.push(RootMove { action, score: *score, previous_score: *previous_score, nodes: *nodes, pv: actions });
+        }
+    }
+
+    if !root_moves.is_empty() {
+        info.root_moves = root_moves;
+        info.root_moves_hash = Some(board.game.rules.hash(board, &info.zobrist));
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+pub const SIGINT: i32 = 2;
+pub const SIGTERM: i32 = 15;
+
+// The signal that asked us to shut down, 0 if none has
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+// Work in progress that wants to wind down itself rather than be cut off
+static BUSY: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+extern "C" {
+    fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    fn _exit(status: i32) -> !;
+}
+
+// Only async-signal-safe work in here: atomics and `_exit`
+#[cfg(unix)]
+extern "C" fn handle_signal(signum: i32) {
+    SIGNAL.store(signum, Ordering::SeqCst);
+
+    if BUSY.load(Ordering::SeqCst) == 0 {
+        unsafe { _exit(128 + signum) };
+    }
+}
+
+pub fn install_handlers() {
+    #[cfg(unix)]
+    unsafe {
+        signal(SIGINT, handle_signal);
+        signal(SIGTERM, handle_signal);
+    }
+}
+
+pub fn shutdown_requested() -> bool {
+    SIGNAL.load(Ordering::Relaxed) != 0
+}
+
+// Shell convention, 128 + the signal number
+pub fn exit_code() -> i32 {
+    128 + SIGNAL.load(Ordering::SeqCst)
+}
+
+// While one of these is alive a signal only requests a shutdown, the holder polls
+// `shutdown_requested` and finishes up; with none alive the process exits immediately
+pub struct Busy;
+
+pub fn busy() -> Busy {
+    BUSY.fetch_add(1, Ordering::SeqCst);
+    Busy
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        BUSY.fetch_sub(1, Ordering::SeqCst);
+    }
+}
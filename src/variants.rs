@@ -0,0 +1,85 @@
+// Every variant the engine knows how to set up, along with a handful of
+// positions used to sanity check it (`bench variants`)
+
+use crate::search::SearchInfo;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameKind {
+    Chess
+}
+
+pub struct Variant {
+    pub name: &'static str,
+    pub game: GameKind,
+    // Use `is_noisy_general` and `eval_primitive` rather than the chess-specific paths
+    pub generic: bool,
+    // Piece indices that can't be given up (kings), and weak non-sliding pieces (pawns);
+    // a side with nothing else left is the zugzwang-prone case null move pruning avoids
+    pub royal_pieces: &'static [usize],
+    pub filler_pieces: &'static [usize],
+    // Pieces that can't mate alone (knights, bishops); one of these against a bare royal is a dead draw
+    pub minor_pieces: &'static [usize],
+    pub positions: &'static [&'static str],
+    // Known move counts, checked by `validate`
+    pub perft: &'static [PerftCase]
+}
+
+pub struct PerftCase {
+    pub fen: &'static str,
+    pub depth: u32,
+    pub nodes: u64
+}
+
+const CHESS_POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"
+];
+
+// The usual movegen positions; depths kept low enough to finish in a few seconds
+const CHESS_PERFT: &[PerftCase] = &[
+    PerftCase { fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", depth: 4, nodes: 197_281 },
+    PerftCase { fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", depth: 3, nodes: 97_862 },
+    PerftCase { fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", depth: 4, nodes: 43_238 },
+    PerftCase { fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", depth: 3, nodes: 9_467 },
+    PerftCase { fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", depth: 3, nodes: 62_379 },
+    PerftCase { fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", depth: 3, nodes: 89_890 }
+];
+
+pub const VARIANTS: &[Variant] = &[
+    Variant {
+        name: "chess",
+        game: GameKind::Chess,
+        generic: false,
+        royal_pieces: &[ 5 ],
+        filler_pieces: &[ 0 ],
+        minor_pieces: &[ 1, 2 ],
+        positions: CHESS_POSITIONS,
+        perft: CHESS_PERFT
+    },
+    // Standard chess run through the variant-agnostic code paths
+    Variant {
+        name: "chess-generic",
+        game: GameKind::Chess,
+        generic: true,
+        royal_pieces: &[ 5 ],
+        filler_pieces: &[ 0 ],
+        minor_pieces: &[ 1, 2 ],
+        positions: CHESS_POSITIONS,
+        perft: CHESS_PERFT
+    }
+];
+
+pub fn find_variant(name: &str) -> Option<&'static Variant> {
+    VARIANTS.iter().find(|variant| variant.name == name)
+}
+
+pub fn configure_search(info: &mut SearchInfo, variant: &Variant) {
+    info.generic = variant.generic;
+    info.royal_pieces = variant.royal_pieces.to_vec();
+    info.filler_pieces = variant.filler_pieces.to_vec();
+    info.minor_pieces = variant.minor_pieces.to_vec();
+}